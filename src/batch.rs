@@ -0,0 +1,189 @@
+// --- ディレクトリ単位の一括変換 ---
+//
+// 入力ディレクトリ以下の`.md`をすべてHTMLに変換し、同じ構成で出力ディレクトリに書き出す。
+// 相対リンクの`.md`は`.html`に書き換え、参照している画像などのファイルもコピーする。
+// 出力が入力より新しいファイルは変更なしとみなして書き出さない。
+
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use pulldown_cmark::CowStr;
+
+use crate::cli::BatchArgs;
+use crate::convert::fallback_title;
+use crate::encoding::{read_file_as, rewrite_as_utf8};
+use crate::export::{document_title, standalone_html};
+use crate::front_matter::split_front_matter;
+use crate::links::{is_relative_url, resolve_path, rewrite_link_urls, split_url};
+use crate::markdown::{is_markdown, markdown_events, render_html};
+use crate::read_sorted_entries;
+
+#[derive(Default)]
+pub struct BatchSummary {
+    pub converted: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub copied: usize, // コピーした画像などのファイル
+}
+
+pub fn run_batch(args: &BatchArgs) -> io::Result<BatchSummary> {
+    let template = args.export.load_template()?;
+    let src_dir = dunce::canonicalize(&args.dir)?;
+    if !src_dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("ディレクトリではありません: {}", args.dir.display()),
+        ));
+    }
+    fs::create_dir_all(&args.output)?;
+    let out_dir = dunce::canonicalize(&args.output)?;
+
+    let mut batch = Batch {
+        args,
+        template,
+        src_dir,
+        out_dir,
+        assets: BTreeSet::new(),
+        summary: BatchSummary::default(),
+    };
+    let mut files = Vec::new();
+    batch.collect_markdown(&batch.src_dir, &mut files)?;
+    for file in &files {
+        if let Err(e) = batch.convert_file(file) {
+            batch.summary.failed += 1;
+            eprintln!("失敗: {}: {}", batch.relative(file).display(), e);
+        }
+    }
+    for asset in std::mem::take(&mut batch.assets) {
+        if let Err(e) = batch.copy_asset(&asset) {
+            batch.summary.failed += 1;
+            eprintln!("失敗: {}: {}", batch.relative(&asset).display(), e);
+        }
+    }
+    Ok(batch.summary)
+}
+
+struct Batch<'a> {
+    args: &'a BatchArgs,
+    template: Option<String>, // 読み込み済みのテンプレート
+    src_dir: PathBuf,
+    out_dir: PathBuf,
+    assets: BTreeSet<PathBuf>, // 参照されているファイル（入力ディレクトリ内の絶対パス）
+    summary: BatchSummary,
+}
+
+impl Batch<'_> {
+    // エクスプローラーと同じ順序でたどる。隠しディレクトリと出力先は除く
+    fn collect_markdown(&self, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for path in read_sorted_entries(dir)? {
+            if path.is_dir() {
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                if !hidden && path != self.out_dir {
+                    self.collect_markdown(&path, files)?;
+                }
+            } else if is_markdown(&path) {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.src_dir).unwrap_or(path)
+    }
+
+    fn convert_file(&mut self, file: &Path) -> io::Result<()> {
+        let relative = self.relative(file).to_path_buf();
+        let decoded = read_file_as(file, self.args.encoding.encoding)?;
+        if self.args.encoding.to_utf8 && rewrite_as_utf8(file, &decoded)? && !self.args.quiet {
+            println!("UTF-8に変換: {}（元は{}）", relative.display(), decoded.label());
+        }
+        let markdown_input = decoded.text;
+
+        // 参照しているファイルは、HTMLを書き出さない場合でもコピー対象に含める
+        let base_dir = file.parent().unwrap_or(&self.src_dir).to_path_buf();
+        let events = markdown_events(&markdown_input)
+            .map(|event| {
+                rewrite_link_urls(event, |url, is_image| self.rewrite_url(url, &base_dir, is_image))
+            })
+            .collect::<Vec<_>>();
+
+        let output = self.out_dir.join(&relative).with_extension("html");
+        if !self.args.force && is_up_to_date(file, &output) {
+            self.summary.skipped += 1;
+            return Ok(());
+        }
+
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut html_output = render_html(events.into_iter(), self.args.export.html_options());
+        if self.args.export.is_standalone() {
+            let title = document_title(&markdown_input).unwrap_or_else(|| fallback_title(file));
+            html_output = standalone_html(
+                &html_output,
+                &title,
+                split_front_matter(&markdown_input).0.as_ref(),
+                self.template.as_deref(),
+            );
+        }
+        fs::write(&output, html_output)?;
+        self.summary.converted += 1;
+        if !self.args.quiet {
+            println!("変換: {}", relative.display());
+        }
+        Ok(())
+    }
+
+    // 相対リンクの.mdを.htmlに書き換え、ローカルのファイルへの参照を記録する
+    fn rewrite_url<'e>(&mut self, url: CowStr<'e>, base_dir: &Path, is_image: bool) -> CowStr<'e> {
+        if !is_relative_url(&url) {
+            return url;
+        }
+        // "#"以降と"?"以降はそのまま残す
+        let (path_part, suffix) = split_url(&url);
+        if path_part.is_empty() {
+            return url;
+        }
+
+        if !is_image && path_part.to_ascii_lowercase().ends_with(".md") {
+            let stem = &path_part[..path_part.len() - ".md".len()];
+            return format!("{}.html{}", stem, suffix).into();
+        }
+
+        // 入力ディレクトリの外を指すファイルはコピーしない
+        let target = resolve_path(base_dir, path_part);
+        if target.starts_with(&self.src_dir) && target.is_file() {
+            self.assets.insert(target);
+        }
+        url
+    }
+
+    fn copy_asset(&mut self, asset: &Path) -> io::Result<()> {
+        let destination = self.out_dir.join(self.relative(asset));
+        if !self.args.force && is_up_to_date(asset, &destination) {
+            return Ok(());
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(asset, &destination)?;
+        self.summary.copied += 1;
+        Ok(())
+    }
+}
+
+// 出力先が存在し、元のファイル以降に更新されていれば変換し直す必要はない
+fn is_up_to_date(source: &Path, output: &Path) -> bool {
+    let modified = |path: &Path| -> Option<SystemTime> { fs::metadata(path).ok()?.modified().ok() };
+    match (modified(source), modified(output)) {
+        (Some(source), Some(output)) => output >= source,
+        _ => false,
+    }
+}
@@ -0,0 +1,90 @@
+// --- ブラウザでのプレビュー ---
+//
+// MarkdownをHTML文書に変換してユーザーごとのキャッシュディレクトリに書き出し、ブラウザで開く。
+// 書き出すファイルは元のファイルごとに固定の名前にするので、何度開いても同じファイルが更新される。
+// 他のユーザーも書き込める共有の一時ディレクトリは、置かれたシンボリックリンクを
+// たどって書き込んでしまうおそれがあるので使わない。
+// 相対パスの画像やリンクは元のディレクトリを基準にした"file://"のURLに書き換える。
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::convert::fallback_title;
+use crate::encoding::read_file;
+use crate::export::{document_title, standalone_html};
+use crate::front_matter::split_front_matter;
+use crate::links::{file_url, is_relative_url, resolve_path, rewrite_link_urls, split_url};
+use crate::markdown::{events_to_html, is_markdown, markdown_events};
+
+/// ファイルをブラウザで開く。Markdownは変換した一時ファイルを、それ以外はファイルをそのまま開く
+pub fn open_in_browser(file_path: &Path) -> io::Result<()> {
+    let file_path = dunce::canonicalize(file_path)?;
+    let target = if is_markdown(&file_path) {
+        render_to_cache(&file_path)?
+    } else {
+        file_path
+    };
+    opener::open(&target).map_err(io::Error::other)
+}
+
+// 変換したHTML文書をキャッシュディレクトリに書き出し、そのパスを返す
+fn render_to_cache(file_path: &Path) -> io::Result<PathBuf> {
+    let markdown_input = read_file(file_path)?.text;
+    let base_dir = file_path.parent().unwrap_or(Path::new("/"));
+    let events = markdown_events(&markdown_input).map(|event| {
+        rewrite_link_urls(event, |url, _| {
+            if !is_relative_url(&url) {
+                return url;
+            }
+            let (path_part, suffix) = split_url(&url);
+            if path_part.is_empty() {
+                return url;
+            }
+            format!("{}{}", file_url(&resolve_path(base_dir, path_part)), suffix).into()
+        })
+    });
+    let body = events_to_html(events);
+    let title = document_title(&markdown_input).unwrap_or_else(|| fallback_title(file_path));
+    let html_output = standalone_html(
+        &body,
+        &title,
+        split_front_matter(&markdown_input).0.as_ref(),
+        None,
+    );
+
+    let html_path = cache_path_for(file_path)?;
+    if let Some(parent) = html_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // 新しいファイルに書いてから置き換える（既にあるファイルやリンクの先には書き込まない）
+    let mut writing_name = html_path.clone().into_os_string();
+    writing_name.push(format!(".{}.tmp", std::process::id()));
+    let writing_path = PathBuf::from(writing_name);
+    let result = File::create_new(&writing_path)
+        .and_then(|mut file| file.write_all(html_output.as_bytes()))
+        .and_then(|()| fs::rename(&writing_path, &html_path));
+    if result.is_err() {
+        let _ = fs::remove_file(&writing_path);
+    }
+    result.map(|()| html_path)
+}
+
+// 同じファイルなら毎回同じファイルになるよう、パスのハッシュを名前に含める
+fn cache_path_for(file_path: &Path) -> io::Result<PathBuf> {
+    let cache_dir = dirs::cache_dir().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "キャッシュディレクトリが見つかりません")
+    })?;
+    let mut hasher = DefaultHasher::new();
+    file_path.hash(&mut hasher);
+    let stem = file_path
+        .file_stem()
+        .map_or_else(|| "preview".into(), |s| s.to_string_lossy());
+    Ok(cache_dir
+        .join("peek-preview")
+        .join(format!("{}-{:016x}.html", stem, hasher.finish())))
+}
@@ -0,0 +1,276 @@
+// --- リンクの検査 ---
+//
+// Markdown中のリンク・画像の相対パスが存在するか、"#見出し"がリンク先の見出しのidと
+// 一致するかを調べ、"ファイル:行"の形式で報告する。外部のURLは一覧にするだけで
+// 検査の成否には含めない。エンドポイントを指定したときだけ、そのHTTPサーバーに
+// 問い合わせて状態を表示する（リンク先のサイトへ直接は接続しない）。
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use pulldown_cmark::{Event, LinkType, Tag};
+
+use crate::encoding::read_file;
+use crate::front_matter::split_front_matter;
+use crate::links::{encode_path, is_relative_url, percent_decode, resolve_path, split_url};
+use crate::markdown::{is_markdown, line_starts, markdown_events};
+use crate::read_sorted_entries;
+use crate::slug::assign_heading_ids;
+
+/// エンドポイントの応答を待つ時間
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// リンク切れ
+pub struct Problem {
+    pub file: PathBuf,
+    pub line: usize,
+    pub url: String,
+    pub reason: String,
+}
+
+/// 外部のURLへのリンク
+pub struct ExternalLink {
+    pub file: PathBuf,
+    pub line: usize,
+    pub url: String,
+    pub status: Option<String>, // エンドポイントに問い合わせた結果
+}
+
+#[derive(Default)]
+pub struct CheckReport {
+    pub files: usize,
+    pub links: usize,
+    pub problems: Vec<Problem>,
+    pub external: Vec<ExternalLink>,
+}
+
+impl CheckReport {
+    /// "ファイル:行: …"の形式の報告。ファイル名は`base`からの相対パスで表示する
+    pub fn render(&self, base: &Path, list_external: bool) -> String {
+        let relative = |path: &Path| path.strip_prefix(base).unwrap_or(path).display().to_string();
+        let mut output = String::new();
+        for problem in &self.problems {
+            let _ = writeln!(
+                output,
+                "{}:{}: リンク切れ: {}（{}）",
+                relative(&problem.file),
+                problem.line,
+                problem.url,
+                problem.reason
+            );
+        }
+        if list_external && !self.external.is_empty() {
+            let _ = writeln!(output, "外部リンク:");
+            for link in &self.external {
+                let status = link.status.as_ref().map(|s| format!(" [{}]", s)).unwrap_or_default();
+                let _ = writeln!(output, "  {}:{}: {}{}", relative(&link.file), link.line, link.url, status);
+            }
+        }
+        let _ = writeln!(output, "{}", self.summary());
+        output
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "検査: {}ファイル / リンク: {}件 / リンク切れ: {}件 / 外部リンク: {}件",
+            self.files,
+            self.links,
+            self.problems.len(),
+            self.external.len()
+        )
+    }
+}
+
+/// ファイルまたはディレクトリ以下の`.md`のリンクを調べる。
+/// `endpoint`は外部のURLの状態を問い合わせるURL（`{url}`をリンク先に置き換える）
+pub fn check_links(paths: &[PathBuf], endpoint: Option<&str>) -> io::Result<CheckReport> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = dunce::canonicalize(path)?;
+        if path.is_dir() {
+            collect_markdown(&path, &mut files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    let mut checker = Checker::default();
+    for file in &files {
+        checker.check_file(file);
+    }
+    if let Some(endpoint) = endpoint {
+        let mut statuses = HashMap::new();
+        for link in &mut checker.report.external {
+            let status = statuses
+                .entry(link.url.clone())
+                .or_insert_with(|| query_endpoint(endpoint, &link.url).unwrap_or_else(|e| e.to_string()));
+            link.status = Some(status.clone());
+        }
+    }
+    checker.report.files = files.len();
+    Ok(checker.report)
+}
+
+// 一括変換と同じく、隠しディレクトリは除いてたどる
+fn collect_markdown(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for path in read_sorted_entries(dir)? {
+        if path.is_dir() {
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if !hidden {
+                collect_markdown(&path, files)?;
+            }
+        } else if is_markdown(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Checker {
+    report: CheckReport,
+    anchors: HashMap<PathBuf, Option<HashSet<String>>>, // ファイルごとの見出しのid（読めなければNone）
+}
+
+impl Checker {
+    fn check_file(&mut self, file: &Path) {
+        let markdown_input = match read_file(file) {
+            Ok(decoded) => decoded.text,
+            Err(e) => {
+                self.problem(file, 1, "", format!("読み込めません: {}", e));
+                return;
+            }
+        };
+        let base_dir = file.parent().unwrap_or(Path::new("/"));
+        for (line, link_type, url) in links_with_lines(&markdown_input) {
+            // "<user@example.com>"はURLにスキームが付かないので相対パスと区別する
+            if link_type == LinkType::Email {
+                continue;
+            }
+            self.report.links += 1;
+            if url.starts_with("http://") || url.starts_with("https://") {
+                self.report.external.push(ExternalLink {
+                    file: file.to_path_buf(),
+                    line,
+                    url,
+                    status: None,
+                });
+                continue;
+            }
+            // "mailto:"などのほかのスキームやルートからのパスは調べられない
+            if !url.starts_with('#') && !is_relative_url(&url) {
+                continue;
+            }
+
+            let (path_part, suffix) = split_url(&url);
+            let target = if path_part.is_empty() {
+                file.to_path_buf()
+            } else {
+                resolve_path(base_dir, path_part)
+            };
+            if !target.exists() {
+                self.problem(file, line, &url, "ファイルが見つかりません".to_string());
+                continue;
+            }
+            let Some(fragment) = suffix.split('?').next().and_then(|s| s.strip_prefix('#')) else {
+                continue;
+            };
+            // 見出しのidがわかるのはMarkdownだけなので、それ以外のファイルのアンカーは調べない
+            if fragment.is_empty() || !is_markdown(&target) {
+                continue;
+            }
+            let fragment = percent_decode(fragment);
+            match self.heading_ids(&target) {
+                Some(ids) if !ids.contains(&fragment) => {
+                    self.problem(file, line, &url, format!("見出しが見つかりません: #{}", fragment));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn problem(&mut self, file: &Path, line: usize, url: &str, reason: String) {
+        self.report.problems.push(Problem {
+            file: file.to_path_buf(),
+            line,
+            url: url.to_string(),
+            reason,
+        });
+    }
+
+    // 同じファイルへのリンクは何度もあるので、見出しのidは読み込んだ結果を使い回す
+    fn heading_ids(&mut self, path: &Path) -> Option<&HashSet<String>> {
+        let path = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.anchors
+            .entry(path)
+            .or_insert_with_key(|path| {
+                let markdown_input = read_file(path).ok()?.text;
+                let mut events = markdown_events(&markdown_input).collect::<Vec<_>>();
+                Some(assign_heading_ids(&mut events).into_iter().map(|heading| heading.id).collect())
+            })
+            .as_ref()
+    }
+}
+
+// リンク・画像ごとに（行番号, 種類, URL）を返す
+fn links_with_lines(markdown_input: &str) -> Vec<(usize, LinkType, String)> {
+    // フロントマターを除いた本文の位置を、元の文書の位置に戻して行番号を数える
+    let (_, body) = split_front_matter(markdown_input);
+    let body_start = markdown_input.len() - body.len();
+    let line_starts = line_starts(markdown_input);
+    markdown_events(markdown_input)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::Link { link_type, dest_url, .. } | Tag::Image { link_type, dest_url, .. }) => {
+                // 先頭がその位置以前にある行の数が、1始まりの行番号になる
+                let line = line_starts.partition_point(|&start| start <= body_start + range.start);
+                Some((line, link_type, dest_url.into_string()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// エンドポイントに`url`の状態を問い合わせ、応答のステータス行（"200 OK"など）を返す。
+/// 外部への接続は利用者が用意したサーバーに任せるので、ここでは平文のHTTPだけを扱う
+fn query_endpoint(endpoint: &str, url: &str) -> io::Result<String> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_string());
+    // クエリの値として渡すので"/"も"%2F"にする
+    let encoded = encode_path(url).replace('/', "%2F");
+    let request_url = if endpoint.contains("{url}") {
+        endpoint.replace("{url}", &encoded)
+    } else {
+        format!("{}{}", endpoint, encoded)
+    };
+    let rest = request_url
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid("エンドポイントはhttp://のURLで指定してください"))?;
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let path = if path.is_empty() { "/" } else { path };
+    let address = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+
+    let mut stream = TcpStream::connect(&address)?;
+    stream.set_read_timeout(Some(ENDPOINT_TIMEOUT))?;
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: peek\r\nConnection: close\r\n\r\n",
+        path, host
+    )?;
+    stream.flush()?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    // "HTTP/1.1 200 OK" → "200 OK"
+    match status_line.trim_end().split_once(' ') {
+        Some((version, status)) if version.starts_with("HTTP/") => Ok(status.to_string()),
+        _ => Err(invalid("エンドポイントの応答を解釈できません")),
+    }
+}
@@ -0,0 +1,210 @@
+// --- コマンドライン引数 ---
+//
+// 引数なし（またはディレクトリ指定）ならエクスプローラーを起動し、
+// サブコマンドが指定されていればTUIを使わずに処理して終了する。
+
+use std::{fs, io, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use encoding_rs::Encoding;
+
+use crate::encoding::encoding_for_label;
+use crate::markdown::HtmlOptions;
+
+// 終了コード（0は正常終了、1はその他のエラー、2はclapが使う引数の誤り）
+pub const EXIT_NOT_MARKDOWN: u8 = 3;
+pub const EXIT_NOT_FOUND: u8 = 4;
+pub const EXIT_CLIPBOARD_FAILED: u8 = 5;
+
+#[derive(Parser)]
+#[command(
+    name = "peek",
+    version,
+    about = "ターミナルでMarkdownをプレビューし、HTMLへの変換結果を確認するツール",
+    after_help = "終了コード:\n  0  正常終了\n  1  その他のエラー\n  2  引数の誤り\n  3  Markdownファイル（.md）ではない\n  4  ファイルが見つからない\n  5  クリップボードへのコピーに失敗（--require-clipboard指定時）"
+)]
+pub struct Cli {
+    /// エクスプローラーを開くディレクトリ（省略時はカレントディレクトリ）
+    #[arg(value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// `peek convert FILE`の旧形式
+    #[arg(short = 'm', value_name = "FILE", hide = true, conflicts_with = "dir")]
+    pub markdown: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// MarkdownファイルをHTMLに変換し、標準出力とクリップボードに出力する
+    Convert(ConvertArgs),
+
+    /// ディレクトリ以下のMarkdownをまとめてHTMLに変換し、同じ構成で出力する
+    Batch(BatchArgs),
+
+    /// ディレクトリをHTTPで公開し、Markdownを変換して表示する（変更時は自動で再読み込み）
+    Serve(ServeArgs),
+
+    /// Markdownの相対リンクと"#見出し"のリンク切れを調べる（リンク切れがあれば終了コード1）
+    Check(CheckArgs),
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// 変換するMarkdownファイル（`-`で標準入力から読む）
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// HTMLを標準出力ではなくファイルに書き出す
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// クリップボードにコピーしない
+    #[arg(long)]
+    pub no_clipboard: bool,
+
+    /// クリップボードにコピーできなければエラーにする（通常は警告のみ）
+    #[arg(long, conflicts_with = "no_clipboard")]
+    pub require_clipboard: bool,
+
+    /// HTMLを標準出力に出すだけにする（クリップボード・ファイル・メッセージなし）
+    #[arg(long, conflicts_with_all = ["output", "no_clipboard", "require_clipboard", "quiet"])]
+    pub stdout_only: bool,
+
+    /// 完了メッセージを表示しない
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    #[command(flatten)]
+    pub export: ExportArgs,
+
+    #[command(flatten)]
+    pub encoding: EncodingArgs,
+}
+
+impl ConvertArgs {
+    /// 旧形式の`-m FILE`は、オプションなしの`convert FILE`と同じ動作にする
+    pub fn from_file(file: PathBuf) -> Self {
+        Self {
+            file,
+            output: None,
+            no_clipboard: false,
+            require_clipboard: false,
+            stdout_only: false,
+            quiet: false,
+            export: ExportArgs::default(),
+            encoding: EncodingArgs::default(),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// 変換するMarkdownがあるディレクトリ
+    #[arg(value_name = "DIR")]
+    pub dir: PathBuf,
+
+    /// HTMLの出力先ディレクトリ（なければ作成する）
+    #[arg(short, long, value_name = "DIR")]
+    pub output: PathBuf,
+
+    /// 変更のないファイルも変換し直す
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// 変換したファイルの一覧を表示しない（集計とエラーは表示する）
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    #[command(flatten)]
+    pub export: ExportArgs,
+
+    #[command(flatten)]
+    pub encoding: EncodingArgs,
+}
+
+/// HTML文書として書き出すためのオプション（`convert`と`batch`で共通）
+#[derive(Args, Default)]
+pub struct ExportArgs {
+    /// <head>とスタイルシートを含む、単体で開けるHTML文書として書き出す
+    #[arg(short, long)]
+    pub standalone: bool,
+
+    /// 文書のテンプレート（{{title}}, {{css}}, {{content}} を置き換える。--standalone を含む）
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// 本文の先頭に見出しの目次を入れる
+    #[arg(long)]
+    pub toc: bool,
+
+    /// 見出しの横に"¶"のリンク（見出しへのアンカー）を付ける
+    #[arg(long)]
+    pub anchors: bool,
+}
+
+impl ExportArgs {
+    pub fn is_standalone(&self) -> bool {
+        self.standalone || self.template.is_some()
+    }
+
+    pub fn html_options(&self) -> HtmlOptions {
+        HtmlOptions {
+            toc: self.toc,
+            anchors: self.anchors,
+        }
+    }
+
+    /// テンプレートの読み込み。指定がなければNone（標準のテンプレートを使う）
+    pub fn load_template(&self) -> io::Result<Option<String>> {
+        self.template.as_ref().map(fs::read_to_string).transpose()
+    }
+}
+
+/// 入力の文字コードの扱い（`convert`と`batch`で共通）
+#[derive(Args, Default)]
+pub struct EncodingArgs {
+    /// 入力の文字コード（shift_jis, euc-jp, utf-16leなど。省略時は自動で判定する）
+    #[arg(long, value_name = "NAME", value_parser = parse_encoding)]
+    pub encoding: Option<&'static Encoding>,
+
+    /// UTF-8以外で書かれた入力ファイルを、BOMなしのUTF-8に書き換える（入力ファイル自体を変更する）
+    #[arg(long)]
+    pub to_utf8: bool,
+}
+
+fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+    encoding_for_label(label).ok_or_else(|| format!("不明な文字コードです: {}", label))
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// 公開するディレクトリ（省略時はカレントディレクトリ）
+    #[arg(value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// 待ち受けるポート番号
+    #[arg(short, long, default_value_t = 8000)]
+    pub port: u16,
+
+    /// 起動後にブラウザで開く
+    #[arg(long)]
+    pub open: bool,
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// 調べるMarkdownファイルまたはディレクトリ（省略時はカレントディレクトリ）
+    #[arg(value_name = "PATH", default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    /// 外部のURLの状態を問い合わせるHTTPのURL（{url}をリンク先に置き換える。結果は成否に含めない）
+    #[arg(long, value_name = "URL")]
+    pub endpoint: Option<String>,
+
+    /// 外部リンクの一覧を表示しない
+    #[arg(short, long)]
+    pub quiet: bool,
+}
@@ -0,0 +1,164 @@
+// --- MarkdownからHTMLへの変換（CUIモード） ---
+
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use arboard::Clipboard;
+use encoding_rs::Encoding;
+
+use crate::cli::{ConvertArgs, EXIT_CLIPBOARD_FAILED, EXIT_NOT_FOUND, EXIT_NOT_MARKDOWN};
+use crate::encoding::{Decoded, decode_with, read_file_as, rewrite_as_utf8};
+use crate::export::{document_title, standalone_html};
+use crate::front_matter::split_front_matter;
+use crate::markdown::{is_markdown, markdown_events, render_html};
+
+/// 変換の失敗理由。理由ごとに終了コードを分ける
+pub enum ConvertError {
+    NotMarkdown(PathBuf),
+    NotFound(PathBuf),
+    Io(PathBuf, io::Error),
+    Clipboard(arboard::Error),
+}
+
+impl ConvertError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            ConvertError::Io(..) => ExitCode::FAILURE,
+            ConvertError::NotMarkdown(_) => ExitCode::from(EXIT_NOT_MARKDOWN),
+            ConvertError::NotFound(_) => ExitCode::from(EXIT_NOT_FOUND),
+            ConvertError::Clipboard(_) => ExitCode::from(EXIT_CLIPBOARD_FAILED),
+        }
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::NotMarkdown(path) => write!(
+                f,
+                "エラー: マークダウンファイル（.md）ではありません: {}",
+                path.display()
+            ),
+            ConvertError::NotFound(path) => {
+                write!(f, "エラー: ファイルが見つかりません: {}", path.display())
+            }
+            ConvertError::Io(path, e) => write!(f, "エラー: {}: {}", display_name(path), e),
+            ConvertError::Clipboard(e) => {
+                write!(f, "エラー: クリップボードにコピーできませんでした: {}", e)
+            }
+        }
+    }
+}
+
+/// `-`は標準入力を表す
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+fn display_name(path: &Path) -> String {
+    if is_stdin(path) {
+        "標準入力".to_string()
+    } else {
+        path.display().to_string()
+    }
+}
+
+/// 見出しがない文書のタイトル（ファイル名から拡張子を除いたもの）
+pub fn fallback_title(path: &Path) -> String {
+    match path.file_stem() {
+        Some(stem) if !is_stdin(path) => stem.to_string_lossy().into_owned(),
+        _ => "Untitled".to_string(),
+    }
+}
+
+/// 入力を読み、文字コードを判定（`encoding`の指定があればそれを使用）してUTF-8にする
+fn read_input(path: &Path, encoding: Option<&'static Encoding>) -> Result<Decoded, ConvertError> {
+    if is_stdin(path) {
+        let mut input = Vec::new();
+        io::stdin()
+            .read_to_end(&mut input)
+            .map_err(|e| ConvertError::Io(path.to_path_buf(), e))?;
+        return Ok(decode_with(&input, encoding));
+    }
+
+    // 拡張子チェック
+    if !is_markdown(path) {
+        return Err(ConvertError::NotMarkdown(path.to_path_buf()));
+    }
+    read_file_as(path, encoding).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => ConvertError::NotFound(path.to_path_buf()),
+        _ => ConvertError::Io(path.to_path_buf(), e),
+    })
+}
+
+/// 変換したHTMLは標準出力（または`--output`のファイル）にだけ書き出し、
+/// 完了メッセージや警告は標準エラー出力に出す。
+/// クリップボードは使えなくても変換自体は続ける（`--require-clipboard`なら失敗扱い）
+pub fn run_convert(args: &ConvertArgs) -> Result<(), ConvertError> {
+    // ファイル（`-`なら標準入力）の読み込み
+    let decoded = read_input(&args.file, args.encoding.encoding)?;
+    if args.encoding.to_utf8 && !is_stdin(&args.file) {
+        let rewritten =
+            rewrite_as_utf8(&args.file, &decoded).map_err(|e| ConvertError::Io(args.file.clone(), e))?;
+        if rewritten && !args.quiet && !args.stdout_only {
+            eprintln!("{}をUTF-8に書き換えました（元は{}）。", args.file.display(), decoded.label());
+        }
+    }
+    let markdown_input = decoded.text;
+
+    // HTML変換
+    let mut html_output = render_html(markdown_events(&markdown_input), args.export.html_options());
+    if args.export.is_standalone() {
+        let template = args
+            .export
+            .load_template()
+            .map_err(|e| ConvertError::Io(args.export.template.clone().unwrap_or_default(), e))?;
+        let title = document_title(&markdown_input).unwrap_or_else(|| fallback_title(&args.file));
+        html_output = standalone_html(
+            &html_output,
+            &title,
+            split_front_matter(&markdown_input).0.as_ref(),
+            template.as_deref(),
+        );
+    }
+
+    // 出力先が指定されていればファイルへ、なければ標準出力へ
+    match &args.output {
+        Some(output) => {
+            fs::write(output, &html_output).map_err(|e| ConvertError::Io(output.clone(), e))?;
+        }
+        None => print!("{}", html_output),
+    }
+
+    // クリップボードに登録
+    let mut copied = false;
+    if !args.no_clipboard && !args.stdout_only {
+        let result = Clipboard::new().and_then(|mut clipboard| clipboard.set_text(&html_output));
+        match result {
+            Ok(()) => copied = true,
+            Err(e) if args.require_clipboard => return Err(ConvertError::Clipboard(e)),
+            Err(e) => {
+                if !args.quiet {
+                    eprintln!("警告: クリップボードにコピーできませんでした: {}", e);
+                }
+            }
+        }
+    }
+
+    if !args.quiet && !args.stdout_only {
+        let mut done = vec!["HTMLへの変換"];
+        if args.output.is_some() {
+            done.push("ファイルへの書き出し");
+        }
+        if copied {
+            done.push("クリップボードへの登録");
+        }
+        eprintln!("{}が完了しました。", done.join("と"));
+    }
+
+    Ok(())
+}
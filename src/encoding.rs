@@ -0,0 +1,256 @@
+// --- 文字コードの判定 ---
+//
+// 古い文書にはShift_JISやEUC-JP、UTF-16で書かれたものがあるので、読み込むときに文字コードを
+// 判定してUTF-8の文字列にする。BOMがあればそれに従い、なければUTF-8として正しいかを調べ、
+// だめならShift_JISとEUC-JPのうち日本語として自然な方を選ぶ。
+// 判定を誤ったときのために、文字コードを指定して読み直すこともできる。
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use encoding_rs::{Encoding, EUC_JP, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8};
+
+/// 判定に使う先頭のバイト数
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// UTF-8の文字列にした文書
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    pub bom: bool,        // 先頭にBOMがあった
+    pub had_errors: bool, // 変換できないバイトを"�"に置き換えた
+}
+
+impl Decoded {
+    /// フッターなどに表示する名前（"Shift_JIS"、"UTF-8 (BOM)"など）
+    pub fn label(&self) -> String {
+        let mut label = self.encoding.name().to_string();
+        if self.bom {
+            label.push_str(" (BOM)");
+        }
+        if self.had_errors {
+            label.push_str(" (invalid bytes)");
+        }
+        label
+    }
+
+    /// BOMなしのUTF-8で書かれていたか（UTF-8に変換し直す必要がないか）
+    pub fn is_plain_utf8(&self) -> bool {
+        self.encoding == UTF_8 && !self.bom && !self.had_errors
+    }
+}
+
+/// ファイルを読み、文字コードを判定してUTF-8の文字列にする
+pub fn read_file(path: &Path) -> io::Result<Decoded> {
+    read_file_as(path, None)
+}
+
+/// ファイルを読む。`encoding`がNoneなら文字コードを判定する
+pub fn read_file_as(path: &Path, encoding: Option<&'static Encoding>) -> io::Result<Decoded> {
+    Ok(decode_with(&fs::read(path)?, encoding))
+}
+
+/// `encoding`がNoneなら文字コードを判定し、指定があればその文字コードとして読む
+pub fn decode_with(bytes: &[u8], encoding: Option<&'static Encoding>) -> Decoded {
+    match encoding {
+        Some(encoding) => decode_as(bytes, encoding),
+        None => decode(bytes),
+    }
+}
+
+/// 文字コードを判定してUTF-8の文字列にする
+pub fn decode(bytes: &[u8]) -> Decoded {
+    decode_as(bytes, detect(bytes))
+}
+
+/// 指定した文字コードとして読む。BOMがあればBOMの文字コードを優先する
+pub fn decode_as(bytes: &[u8], encoding: &'static Encoding) -> Decoded {
+    let (text, encoding, had_errors) = encoding.decode(bytes);
+    Decoded {
+        text: text.into_owned(),
+        encoding,
+        bom: Encoding::for_bom(bytes).is_some(),
+        had_errors,
+    }
+}
+
+/// UTF-8以外で書かれていたファイルを、BOMなしのUTF-8で書き直す。書き直したらtrueを返す。
+/// 変換できないバイトがあった場合は、元の内容が失われるので書き直さない。
+/// 途中で失敗しても元のファイルが壊れないよう、同じディレクトリの一時ファイルに書いてから置き換える
+pub fn rewrite_as_utf8(path: &Path, decoded: &Decoded) -> io::Result<bool> {
+    if decoded.is_plain_utf8() {
+        return Ok(false);
+    }
+    if decoded.had_errors {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}として変換できないバイトがあるため、UTF-8に書き換えません", decoded.encoding.name()),
+        ));
+    }
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+    let result = write_synced(&temp_path, decoded.text.as_bytes(), fs::metadata(path)?.permissions())
+        .and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.map(|()| true)
+}
+
+// 新しいファイルに書き込み、ディスクに書き出されるまで待つ
+fn write_synced(path: &Path, contents: &[u8], permissions: fs::Permissions) -> io::Result<()> {
+    let mut file = File::create_new(path)?;
+    file.write_all(contents)?;
+    file.set_permissions(permissions)?;
+    file.sync_all()
+}
+
+/// "sjis"・"euc-jp"・"utf-16le"などの名前から文字コードを探す（大文字・小文字は区別しない）
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    match label.to_ascii_lowercase().as_str() {
+        "cp932" | "ms932" => Some(SHIFT_JIS),
+        "eucjp" | "ujis" => Some(EUC_JP),
+        "utf8" => Some(UTF_8),
+        "utf16" | "utf16le" => Some(UTF_16LE),
+        "utf16be" => Some(UTF_16BE),
+        label => Encoding::for_label(label.as_bytes()),
+    }
+}
+
+/// ファイルの先頭だけを読んで、テキストではないかを調べる
+pub fn is_binary_file(path: &Path) -> io::Result<bool> {
+    let mut head = Vec::new();
+    File::open(path)?.take(SNIFF_BYTES as u64).read_to_end(&mut head)?;
+    Ok(is_binary(&head))
+}
+
+/// NULを含み、UTF-16とも判定できないものはテキストではないとみなす
+pub fn is_binary(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    head.contains(&0) && Encoding::for_bom(bytes).is_none() && utf16_without_bom(head).is_none()
+}
+
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    // ASCIIだけのUTF-16はUTF-8としても正しいので、先に調べる
+    let head = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    if let Some(encoding) = utf16_without_bom(head) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+
+    // 正しく変換できる候補のうち、日本語として自然なものを選ぶ。
+    // どれも正しくなければ、置き換えた文字が最も少ないものにする
+    let candidates = [UTF_8, SHIFT_JIS, EUC_JP];
+    let valid = candidates
+        .iter()
+        .filter_map(|&encoding| {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .map(|text| (encoding, japanese_score(&text)))
+        })
+        .max_by_key(|&(_, score)| score);
+    if let Some((encoding, _)) = valid {
+        return encoding;
+    }
+    candidates
+        .into_iter()
+        .min_by_key(|encoding| {
+            let (text, _) = encoding.decode_without_bom_handling(bytes);
+            text.matches('\u{fffd}').count()
+        })
+        .unwrap_or(UTF_8)
+}
+
+// ASCIIの文字が多いUTF-16では、上位か下位のバイトの片方ばかりが0になる
+fn utf16_without_bom(head: &[u8]) -> Option<&'static Encoding> {
+    let pairs = head.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let zeros_at = |offset: usize| head.chunks_exact(2).filter(|pair| pair[offset] == 0).count();
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    if odd * 10 >= pairs * 3 && even * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even * 10 >= pairs * 3 && odd * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+// ひらがな・カタカナ・漢字が多いほど高く、半角カナや制御文字が多いほど低くする。
+// EUC-JPの文書をShift_JISとして読むと、半角カナが大量に現れる
+fn japanese_score(text: &str) -> i64 {
+    text.chars()
+        .map(|c| match c {
+            '\u{3040}'..='\u{30ff}' | '\u{4e00}'..='\u{9fff}' | '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff5e}' => 2,
+            '\u{ff61}'..='\u{ff9f}' => -1,
+            c if c.is_control() && !c.is_whitespace() => -2,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "# 日本語の文書\n\nこれはテストです。カタカナも含みます。\n";
+
+    fn encode(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+        encoding.encode(text).0.into_owned()
+    }
+
+    fn encode_utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() })
+            .collect()
+    }
+
+    #[test]
+    fn detects_shift_jis_and_euc_jp() {
+        assert_eq!(detect(&encode(SHIFT_JIS, TEXT)), SHIFT_JIS);
+        assert_eq!(detect(&encode(EUC_JP, TEXT)), EUC_JP);
+        assert_eq!(detect(TEXT.as_bytes()), UTF_8);
+        assert_eq!(decode(&encode(EUC_JP, TEXT)).text, TEXT);
+    }
+
+    #[test]
+    fn detects_utf16_without_bom() {
+        let text = "# Title\n\nplain ASCII text\n";
+        assert_eq!(detect(&encode_utf16(text, false)), UTF_16LE);
+        assert_eq!(detect(&encode_utf16(text, true)), UTF_16BE);
+        let decoded = decode(&encode_utf16(text, false));
+        assert_eq!(decoded.text, text);
+        assert!(!decoded.bom);
+        assert!(!is_binary(&encode_utf16(text, true)));
+    }
+
+    #[test]
+    fn strips_bom() {
+        let mut bytes = b"\xef\xbb\xbf".to_vec();
+        bytes.extend_from_slice(TEXT.as_bytes());
+        let decoded = decode(&bytes);
+        assert_eq!(decoded.encoding, UTF_8);
+        assert_eq!(decoded.text, TEXT);
+        assert!(decoded.bom);
+        assert!(!decoded.is_plain_utf8());
+
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(encode_utf16(TEXT, false));
+        let decoded = decode(&bytes);
+        assert_eq!(decoded.encoding, UTF_16LE);
+        assert_eq!(decoded.text, TEXT);
+        assert!(decoded.bom);
+    }
+}
@@ -0,0 +1,99 @@
+// --- 単体で開けるHTML文書の書き出し ---
+//
+// HTML断片をテンプレートに埋め込み、文字コードの指定とスタイルシートを含む
+// 完全なHTML5文書にする。テンプレートでは`{{title}}`・`{{css}}`・`{{content}}`と
+// フロントマターの各項目が置き換わる。
+
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+use crate::front_matter::{split_front_matter, FrontMatter};
+use crate::markdown::markdown_events;
+
+/// 標準のテンプレート
+const DEFAULT_TEMPLATE: &str = include_str!("../assets/template.html");
+
+/// GitHub Dark風のスタイルシート（TUIの配色と合わせてある）
+const STYLESHEET: &str = include_str!("../assets/github-dark.css");
+
+/// 本文のHTML断片をテンプレートに埋め込む。`template`がNoneなら標準のテンプレートを使う。
+/// フロントマターの各項目も`{{date}}`や`{{author.name}}`のような変数として使える
+pub fn standalone_html(
+    body: &str,
+    title: &str,
+    front_matter: Option<&FrontMatter>,
+    template: Option<&str>,
+) -> String {
+    let template = template.unwrap_or(DEFAULT_TEMPLATE);
+    let title = escape_html(title);
+    let mut vars = vec![("title", title), ("css", STYLESHEET.to_string()), ("content", body.to_string())];
+    // 組み込みの変数と同じ名前の項目は組み込みの方を優先する
+    for field in front_matter.map_or(&[][..], |front_matter| &front_matter.fields) {
+        if !vars.iter().any(|(key, _)| *key == field.key) {
+            vars.push((&field.key, escape_html(&field.value)));
+        }
+    }
+    let vars = vars.iter().map(|(key, value)| (*key, value.as_str())).collect::<Vec<_>>();
+    render_template(template, &vars)
+}
+
+/// 文書のタイトル。フロントマターの`title`、なければ最初の見出しの文字列
+pub fn document_title(markdown_input: &str) -> Option<String> {
+    if let (Some(front_matter), _) = split_front_matter(markdown_input)
+        && let Some(title) = front_matter.get("title").filter(|title| !title.is_empty())
+    {
+        return Some(title.to_string());
+    }
+
+    let mut heading: Option<String> = None;
+    for event in markdown_events(markdown_input) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => heading = Some(String::new()),
+            Event::End(TagEnd::Heading(_)) => {
+                let title = heading.take().unwrap_or_default().trim().to_string();
+                if !title.is_empty() {
+                    return Some(title);
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = &mut heading {
+                    heading.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `{{name}}`をそれぞれの値に置き換える。未知の名前は空文字列にする
+pub fn render_template(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..start + end].trim();
+        if let Some((_, value)) = vars.iter().find(|(key, _)| *key == name) {
+            output.push_str(value);
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
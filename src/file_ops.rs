@@ -0,0 +1,234 @@
+// --- エクスプローラーでのファイル操作 ---
+//
+// 作成・名前の変更・コピー・移動・削除を行い、直前の操作を取り消すための情報を返す。
+// 削除は消さずにゴミ箱（XDG Trashなど、OSのゴミ箱）へ移す。既にあるファイルは上書きしない。
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub enum FileOp {
+    Mkdir(PathBuf),
+    Touch(PathBuf),
+    Rename(PathBuf, PathBuf), // (元のパス, 新しいパス)。同じディレクトリの中での移動
+    Copy(PathBuf, PathBuf),
+    Move(PathBuf, PathBuf),
+    Trash(PathBuf),
+}
+
+/// 操作を取り消す方法
+pub enum Undo {
+    RemoveDir(PathBuf),     // 作ったディレクトリを消す（空のときだけ）
+    Trash(PathBuf),         // 作ったファイル・ディレクトリをゴミ箱に移す
+    Move(PathBuf, PathBuf), // (今のパス, 元のパス)。元の場所に戻す
+    Restore(PathBuf),       // ゴミ箱に移したものを元のパスに戻す
+}
+
+impl FileOp {
+    /// 操作の後にカーソルを合わせるパス
+    pub fn target(&self) -> &Path {
+        match self {
+            FileOp::Mkdir(path) | FileOp::Touch(path) | FileOp::Trash(path) => path,
+            FileOp::Rename(_, to) | FileOp::Copy(_, to) | FileOp::Move(_, to) => to,
+        }
+    }
+
+    /// 完了メッセージ（例: "名前を変更しました: a.md → b.md"）
+    pub fn describe(&self) -> String {
+        match self {
+            FileOp::Mkdir(path) => format!("ディレクトリを作成しました: {}", file_name(path)),
+            FileOp::Touch(path) => format!("ファイルを作成しました: {}", file_name(path)),
+            FileOp::Rename(from, to) => {
+                format!("名前を変更しました: {} → {}", file_name(from), file_name(to))
+            }
+            FileOp::Copy(from, to) => format!("コピーしました: {} → {}", file_name(from), to.display()),
+            FileOp::Move(from, to) => format!("移動しました: {} → {}", file_name(from), to.display()),
+            FileOp::Trash(path) => format!("ゴミ箱に移動しました: {}", file_name(path)),
+        }
+    }
+
+    /// 操作を行い、取り消す方法を返す
+    pub fn apply(&self) -> io::Result<Undo> {
+        match self {
+            FileOp::Mkdir(path) => {
+                fs::create_dir(path)?;
+                Ok(Undo::RemoveDir(path.clone()))
+            }
+            FileOp::Touch(path) => {
+                fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+                Ok(Undo::Trash(path.clone()))
+            }
+            FileOp::Copy(from, to) => {
+                ensure_vacant(to)?;
+                copy_recursive(from, to)?;
+                Ok(Undo::Trash(to.clone()))
+            }
+            FileOp::Rename(from, to) | FileOp::Move(from, to) => {
+                move_path(from, to)?;
+                Ok(Undo::Move(to.clone(), from.clone()))
+            }
+            FileOp::Trash(path) => {
+                trash::delete(path).map_err(io::Error::other)?;
+                Ok(Undo::Restore(path.clone()))
+            }
+        }
+    }
+}
+
+impl Undo {
+    /// 取り消しを行い、その後にカーソルを合わせるパスを返す
+    pub fn apply(&self) -> io::Result<&Path> {
+        match self {
+            Undo::RemoveDir(path) => {
+                // 作った後に中へ置いたファイルまで消さないよう、空でなければ取り消さない
+                if fs::read_dir(path)?.next().is_some() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::DirectoryNotEmpty,
+                        format!("ディレクトリが空ではありません: {}", file_name(path)),
+                    ));
+                }
+                fs::remove_dir(path)?;
+                Ok(path)
+            }
+            Undo::Trash(path) => {
+                trash::delete(path).map_err(io::Error::other)?;
+                Ok(path)
+            }
+            Undo::Move(from, to) => {
+                move_path(from, to)?;
+                Ok(to)
+            }
+            Undo::Restore(path) => {
+                restore_from_trash(path)?;
+                Ok(path)
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Undo::RemoveDir(path) => format!("取り消しました（ディレクトリを削除: {}）", file_name(path)),
+            Undo::Trash(path) => format!("取り消しました（ゴミ箱に移動: {}）", file_name(path)),
+            Undo::Move(_, to) => format!("取り消しました（元に戻す: {}）", to.display()),
+            Undo::Restore(path) => format!("取り消しました（ゴミ箱から戻す: {}）", file_name(path)),
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| path.to_string_lossy(), |s| s.to_string_lossy())
+        .into_owned()
+}
+
+// 上書きしないよう、移動先・コピー先が空いていることを確かめる
+fn ensure_vacant(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("既に存在します: {}", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+// 別のファイルシステムへはrenameできないので、コピーしてから元を消す
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    ensure_vacant(to)?;
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_recursive(from, to)?;
+            // シンボリックリンクはリンク先ではなくリンク自体を消す
+            if from.symlink_metadata()?.is_dir() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+// シンボリックリンクはたどらずにリンクとして作り直す（親ディレクトリへのリンクで無限に潜らない）
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let file_type = from.symlink_metadata()?.file_type();
+    if file_type.is_symlink() {
+        return copy_symlink(from, to);
+    }
+    if !file_type.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    if to.starts_with(from) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "ディレクトリをその中にはコピーできません",
+        ));
+    }
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+// Windowsではファイルへのリンクとディレクトリへのリンクを作り分ける
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::windows::fs::{symlink_dir, symlink_file, FileTypeExt};
+
+    let target = fs::read_link(from)?;
+    if from.symlink_metadata()?.file_type().is_symlink_dir() {
+        symlink_dir(target, to)
+    } else {
+        symlink_file(target, to)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_symlink(_from: &Path, _to: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "このOSではシンボリックリンクをコピーできません",
+    ))
+}
+
+// ゴミ箱の中から元のパスが一致するもののうち、最後に移したものを戻す
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+fn restore_from_trash(path: &Path) -> io::Result<()> {
+    use trash::os_limited::{list, restore_all};
+
+    ensure_vacant(path)?;
+    let item = list()
+        .map_err(io::Error::other)?
+        .into_iter()
+        .filter(|item| item.original_path() == path)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("ゴミ箱に見つかりません: {}", path.display()),
+            )
+        })?;
+    restore_all([item]).map_err(io::Error::other)
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn restore_from_trash(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "このOSではゴミ箱から元に戻せません",
+    ))
+}
@@ -0,0 +1,189 @@
+// --- ファイル検索（Ctrl-p） ---
+//
+// 表示中のディレクトリ以下のファイルを別スレッドでたどって一覧にし、入力した文字で
+// あいまい検索する。.gitignoreで除外されたファイルと隠しファイルは含めない。
+// 一致の度合いが高い順に並べ、同じ度合いなら短いパスを先にする。
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use ignore::WalkBuilder;
+
+/// 別スレッドから一度に送るファイルの数
+const BATCH_SIZE: usize = 1000;
+
+pub enum FinderAction {
+    None,
+    Close,
+    Open(PathBuf),   // ファイルをプレビューで開く
+    Reveal(PathBuf), // エクスプローラーでファイルのあるディレクトリへ移動する
+}
+
+pub struct FileFinder {
+    pub root: PathBuf,
+    pub files: Vec<String>,                 // rootからの相対パス（たどった順）
+    indexer: Option<Receiver<Vec<String>>>, // たどっている間だけSome
+    pub query: String,
+    pub matches: Vec<usize>, // 一致したファイル（filesの添字）を順位の順に
+    pub selected: usize,     // matchesの中の選択中の位置
+    matcher: SkimMatcherV2,
+}
+
+impl FileFinder {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            files: Vec::new(),
+            indexer: Some(spawn_walker(root)),
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            matcher: SkimMatcherV2::default().smart_case(),
+        }
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.indexer.is_some()
+    }
+
+    /// たどったファイルを取り込み、増えていれば絞り込み直す
+    pub fn poll(&mut self) {
+        let Some(indexer) = &self.indexer else {
+            return;
+        };
+        let before = self.files.len();
+        loop {
+            match indexer.try_recv() {
+                Ok(files) => self.files.extend(files),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.indexer = None;
+                    break;
+                }
+            }
+        }
+        if self.files.len() != before {
+            self.update_matches();
+        }
+    }
+
+    // 入力した文字に一致するファイルを、一致の度合いが高い順に並べ直す
+    fn update_matches(&mut self) {
+        let selected = self.selected_index();
+        if self.query.is_empty() {
+            self.matches = (0..self.files.len()).collect();
+        } else {
+            let mut scored = self
+                .files
+                .iter()
+                .enumerate()
+                .filter_map(|(i, file)| self.matcher.fuzzy_match(file, &self.query).map(|score| (score, i)))
+                .collect::<Vec<_>>();
+            scored.sort_by(|(a_score, a), (b_score, b)| {
+                b_score
+                    .cmp(a_score)
+                    .then_with(|| self.files[*a].len().cmp(&self.files[*b].len()))
+                    .then_with(|| self.files[*a].cmp(&self.files[*b]))
+            });
+            self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        }
+        // 一覧が伸びただけなら選択中のファイルを保つ
+        self.selected = selected
+            .and_then(|selected| self.matches.iter().position(|&i| i == selected))
+            .unwrap_or(0);
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.matches.get(self.selected).copied()
+    }
+
+    /// 選択中のファイルの絶対パス
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.selected_index().map(|i| self.root.join(&self.files[i]))
+    }
+
+    /// 一致した文字の位置（強調表示用、文字単位）
+    pub fn match_positions(&self, file: &str) -> Vec<usize> {
+        if self.query.is_empty() {
+            return Vec::new();
+        }
+        self.matcher
+            .fuzzy_indices(file, &self.query)
+            .map(|(_, positions)| positions)
+            .unwrap_or_default()
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> FinderAction {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return FinderAction::Close,
+            KeyCode::Enter => {
+                return self.selected_path().map_or(FinderAction::None, FinderAction::Open);
+            }
+            KeyCode::Tab => {
+                return self.selected_path().map_or(FinderAction::None, FinderAction::Reveal);
+            }
+            KeyCode::Down => self.move_selection(true),
+            KeyCode::Up => self.move_selection(false),
+            KeyCode::Char('n') if ctrl => self.move_selection(true),
+            KeyCode::Char('p') if ctrl => self.move_selection(false),
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.update_matches();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.selected = 0;
+                self.update_matches();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+                self.update_matches();
+            }
+            _ => {}
+        }
+        FinderAction::None
+    }
+
+    fn move_selection(&mut self, forward: bool) {
+        self.selected = if forward {
+            (self.selected + 1).min(self.matches.len().saturating_sub(1))
+        } else {
+            self.selected.saturating_sub(1)
+        };
+    }
+}
+
+// .gitignore（gitのリポジトリでなくても）を考慮してファイルをたどり、少しずつ送る。
+// 受け取る側がいなくなったら止める
+fn spawn_walker(root: &Path) -> Receiver<Vec<String>> {
+    let root = root.to_path_buf();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let walker = WalkBuilder::new(&root)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        for entry in walker.flatten() {
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+            batch.push(relative.to_string_lossy().into_owned());
+            if batch.len() == BATCH_SIZE && tx.send(std::mem::take(&mut batch)).is_err() {
+                return;
+            }
+        }
+        let _ = tx.send(batch);
+    });
+    rx
+}
+
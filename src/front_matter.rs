@@ -0,0 +1,216 @@
+// --- フロントマター ---
+//
+// 文書の先頭の`---`で囲まれたYAML、または`+++`で囲まれたTOMLを取り出す。
+// 変換の前に本文から取り除き、プレビューのヘッダー、HTMLテンプレートの変数、
+// エクスプローラーの絞り込みに使う。値はすべて表示用の文字列にしておく。
+
+use std::{fs::File, io::Read, path::Path};
+
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::encoding::decode;
+
+/// エクスプローラーで絞り込むときに読む、ファイル先頭のバイト数
+const HEAD_BYTES: u64 = 64 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Yaml => "YAML",
+            Format::Toml => "TOML",
+        }
+    }
+}
+
+pub struct Field {
+    pub key: String,        // 入れ子のキーは"author.name"のように"."でつなぐ
+    pub value: String,      // 表示用の値（配列は", "でつなぐ）
+    pub items: Vec<String>, // 配列の各要素（配列でなければ値そのもの1つ）
+}
+
+pub struct FrontMatter {
+    pub format: Format,
+    pub fields: Vec<Field>,
+}
+
+impl FrontMatter {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|field| field.key == key)
+            .map(|field| field.value.as_str())
+    }
+
+    /// `key`の値（配列ならいずれかの要素）が`value`と一致するか。大文字・小文字は区別しない
+    pub fn matches(&self, key: &str, value: &str) -> bool {
+        self.fields
+            .iter()
+            .filter(|field| field.key == key)
+            .flat_map(|field| &field.items)
+            .any(|item| item.eq_ignore_ascii_case(value))
+    }
+}
+
+/// フロントマターと本文に分ける。フロントマターがなければ文書全体を本文として返す
+pub fn split_front_matter(input: &str) -> (Option<FrontMatter>, &str) {
+    let text = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut lines = text.split_inclusive('\n');
+    let Some(first) = lines.next() else {
+        return (None, input);
+    };
+    let (format, closers): (Format, &[&str]) = match first.trim_end() {
+        "---" => (Format::Yaml, &["---", "..."]),
+        "+++" => (Format::Toml, &["+++"]),
+        _ => return (None, input),
+    };
+
+    let start = first.len();
+    let mut offset = start;
+    for line in lines {
+        if closers.contains(&line.trim_end()) {
+            let source = &text[start..offset];
+            let body = &text[offset + line.len()..];
+            // キーと値の組として読めないもの（水平線と見出しなど）はフロントマターとみなさない
+            return match parse(format, source) {
+                Some(fields) => (Some(FrontMatter { format, fields }), body),
+                None => (None, input),
+            };
+        }
+        offset += line.len();
+    }
+    (None, input)
+}
+
+/// ファイルの先頭だけを読んでフロントマターを取り出す
+pub fn read_front_matter(path: &Path) -> Option<FrontMatter> {
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(HEAD_BYTES).read_to_end(&mut head).ok()?;
+    split_front_matter(&decode(&head).text).0
+}
+
+fn parse(format: Format, source: &str) -> Option<Vec<Field>> {
+    let mut fields = Vec::new();
+    match format {
+        Format::Yaml => {
+            let documents = YamlLoader::load_from_str(source).ok()?;
+            match documents.into_iter().next() {
+                // 空のフロントマターは認めるが、"# 見出し"のようにコメントだけのものは本文とみなす
+                None | Some(Yaml::Null) if source.trim().is_empty() => {}
+                Some(Yaml::Hash(hash)) => {
+                    for (key, value) in hash {
+                        flatten_yaml(&yaml_to_string(&key), &value, &mut fields);
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Format::Toml => {
+            let table = source.parse::<toml::Table>().ok()?;
+            for (key, value) in table {
+                flatten_toml(&key, &value, &mut fields);
+            }
+        }
+    }
+    Some(fields)
+}
+
+fn flatten_yaml(key: &str, value: &Yaml, fields: &mut Vec<Field>) {
+    match value {
+        Yaml::Hash(hash) => {
+            for (child, value) in hash {
+                flatten_yaml(&format!("{}.{}", key, yaml_to_string(child)), value, fields);
+            }
+        }
+        Yaml::Array(items) => push_list(key, items.iter().map(yaml_to_string).collect(), fields),
+        value => push_scalar(key, yaml_to_string(value), fields),
+    }
+}
+
+fn yaml_to_string(value: &Yaml) -> String {
+    match value {
+        Yaml::String(s) | Yaml::Real(s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Array(items) => items.iter().map(yaml_to_string).collect::<Vec<_>>().join(", "),
+        Yaml::Hash(_) | Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => String::new(),
+    }
+}
+
+fn flatten_toml(key: &str, value: &toml::Value, fields: &mut Vec<Field>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (child, value) in table {
+                flatten_toml(&format!("{}.{}", key, child), value, fields);
+            }
+        }
+        toml::Value::Array(items) => push_list(key, items.iter().map(toml_to_string).collect(), fields),
+        value => push_scalar(key, toml_to_string(value), fields),
+    }
+}
+
+fn toml_to_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Array(items) => items.iter().map(toml_to_string).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
+
+fn push_scalar(key: &str, value: String, fields: &mut Vec<Field>) {
+    fields.push(Field {
+        key: key.to_string(),
+        items: vec![value.clone()],
+        value,
+    });
+}
+
+fn push_list(key: &str, items: Vec<String>, fields: &mut Vec<Field>) {
+    fields.push(Field {
+        key: key.to_string(),
+        value: items.join(", "),
+        items,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_yaml_and_toml() {
+        let (front_matter, body) = split_front_matter("---\ntitle: 日記\ntags: [a, b]\n---\n# 本文\n");
+        let front_matter = front_matter.expect("YAML front matter");
+        assert_eq!(front_matter.get("title"), Some("日記"));
+        assert!(front_matter.matches("tags", "B"));
+        assert_eq!(body, "# 本文\n");
+
+        let (front_matter, body) = split_front_matter("+++\ntitle = \"x\"\n+++\nbody\n");
+        assert!(front_matter.is_some_and(|front_matter| front_matter.format == Format::Toml));
+        assert_eq!(body, "body\n");
+    }
+
+    #[test]
+    fn allows_empty_front_matter() {
+        let (front_matter, body) = split_front_matter("---\n---\nbody\n");
+        assert!(front_matter.is_some_and(|front_matter| front_matter.fields.is_empty()));
+        assert_eq!(body, "body\n");
+    }
+
+    #[test]
+    fn horizontal_rules_are_not_front_matter() {
+        // 水平線で囲まれた段落・見出しは本文のまま残す
+        for input in ["---\nただの段落\n---\n", "---\n# 見出し\n---\n", "---\n\n- item\n\n---\n"] {
+            let (front_matter, body) = split_front_matter(input);
+            assert!(front_matter.is_none(), "{:?}", input);
+            assert_eq!(body, input);
+        }
+        // 閉じる行がなければフロントマターではない
+        assert!(split_front_matter("---\ntitle: x\n").0.is_none());
+    }
+}
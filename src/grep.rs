@@ -0,0 +1,277 @@
+// --- ディレクトリ以下の全文検索（:grep） ---
+//
+// 表示中のディレクトリ以下のファイルを別スレッドで一つずつ読み、パターン（正規表現）に
+// 一致する行を見つけたそばから結果の一覧に加える。.gitignoreで除外されたファイル・
+// 隠しファイル・バイナリファイル・大きなファイルは読まない。
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+
+use crate::encoding::{decode, is_binary};
+use crate::large_file::LARGE_FILE_BYTES;
+use crate::markdown::is_markdown;
+
+/// 一致した行の前後に表示する行数
+pub const CONTEXT_LINES: usize = 2;
+
+/// これより多く見つかったら検索をやめる
+pub const MAX_HITS: usize = 10_000;
+
+/// 一致した行がなくても、この数のファイルを読むごとに進み具合を送る
+const PROGRESS_FILES: usize = 100;
+
+pub struct GrepHit {
+    pub path: PathBuf,
+    pub relative: String,            // 一覧に表示するrootからの相対パス
+    pub line: usize,                 // 一致した行（0始まり）
+    pub text: String,                // 一致した行
+    pub ranges: Vec<(usize, usize)>, // 行の中の一致箇所（バイト範囲）
+    pub nth: usize,                  // この行の最初の一致箇所がファイルの中で何番目か（0始まり）
+    pub before: Vec<String>,         // 一致した行の前の行
+    pub after: Vec<String>,          // 一致した行の後の行
+}
+
+// 検索スレッドから送る結果。filesはこれまでに読んだファイルの数
+struct GrepProgress {
+    files: usize,
+    hits: Vec<GrepHit>,
+}
+
+pub enum GrepAction {
+    None,
+    Open, // 選択中の一致箇所をプレビューで開く
+}
+
+pub struct GrepView {
+    pub pattern: String,
+    pub ignore_case: bool,   // パターンに大文字がなければ大文字・小文字を区別しない
+    pub markdown_only: bool, // .mdファイルだけを検索する
+    pub hits: Vec<GrepHit>,
+    pub files_searched: usize,
+    receiver: Option<Receiver<GrepProgress>>, // 検索している間だけSome
+    pub selected: usize,
+    pub top: usize,               // 先頭に表示している結果
+    viewport_rows: usize,         // 一覧に表示できる行数（描画時に更新）
+    pending_count: Option<usize>, // "20j"などの回数指定
+    pending_g: bool,              // "gg"の1文字目を入力済み
+    pub status_message: Option<String>,
+}
+
+impl GrepView {
+    /// `root`以下の検索を別スレッドで始める。パターンが正規表現として正しくなければエラー
+    pub fn start(root: &Path, pattern: &str, markdown_only: bool) -> Result<Self, regex::Error> {
+        let ignore_case = !pattern.chars().any(char::is_uppercase);
+        let regex = RegexBuilder::new(pattern).case_insensitive(ignore_case).build()?;
+        let (tx, rx) = mpsc::channel();
+        let root = root.to_path_buf();
+        thread::spawn(move || search_files(&root, &regex, markdown_only, tx));
+        Ok(Self {
+            pattern: pattern.to_string(),
+            ignore_case,
+            markdown_only,
+            hits: Vec::new(),
+            files_searched: 0,
+            receiver: Some(rx),
+            selected: 0,
+            top: 0,
+            viewport_rows: 1,
+            pending_count: None,
+            pending_g: false,
+            status_message: None,
+        })
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// 検索スレッドから届いた結果を取り込む
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+        loop {
+            match receiver.try_recv() {
+                Ok(progress) => {
+                    self.files_searched = progress.files;
+                    self.hits.extend(progress.hits);
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn selected_hit(&self) -> Option<&GrepHit> {
+        self.hits.get(self.selected)
+    }
+
+    /// 一覧の高さを設定し、選択中の結果が見えるように先頭を合わせる
+    pub fn set_viewport(&mut self, rows: u16) {
+        self.viewport_rows = (rows as usize).max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + self.viewport_rows {
+            self.top = self.selected + 1 - self.viewport_rows;
+        }
+    }
+
+    /// フッターに表示する位置（例: "3/120"）
+    pub fn position_label(&self) -> String {
+        if self.hits.is_empty() {
+            "0/0".to_string()
+        } else {
+            format!("{}/{}", self.selected + 1, self.hits.len())
+        }
+    }
+
+    pub fn pending_keys(&self) -> String {
+        let count = self.pending_count.map(|n| n.to_string()).unwrap_or_default();
+        format!("{}{}", count, if self.pending_g { "g" } else { "" })
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> GrepAction {
+        self.status_message = None; // 操作時に一時メッセージを消す
+        // 数字は次の移動の回数として溜めておく（先頭の0は回数にしない）
+        if let KeyCode::Char(c @ '0'..='9') = key.code
+            && (c != '0' || self.pending_count.is_some())
+        {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            let count = self.pending_count.unwrap_or(0);
+            self.pending_count = Some(count.saturating_mul(10).saturating_add(digit));
+            return GrepAction::None;
+        }
+        let count = self.pending_count.take();
+        let times = count.unwrap_or(1);
+        let pending_g = std::mem::take(&mut self.pending_g);
+        let half_page = (self.viewport_rows / 2).max(1);
+        let page = self.viewport_rows.saturating_sub(2).max(1);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('l') | KeyCode::Right => {
+                return if self.hits.is_empty() { GrepAction::None } else { GrepAction::Open };
+            }
+            KeyCode::Char('d') if ctrl => self.move_down(half_page * times),
+            KeyCode::Char('u') if ctrl => self.move_up(half_page * times),
+            KeyCode::Char('f') if ctrl => self.move_down(page * times),
+            KeyCode::Char('b') if ctrl => self.move_up(page * times),
+            KeyCode::PageDown => self.move_down(page * times),
+            KeyCode::PageUp => self.move_up(page * times),
+            KeyCode::Down | KeyCode::Char('j') => self.move_down(times),
+            KeyCode::Up | KeyCode::Char('k') => self.move_up(times),
+            // "gg"・"G"は先頭・末尾へ、回数指定があればその番号の結果へ移動する
+            KeyCode::Char('g') if pending_g => self.select(times - 1),
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+                self.pending_count = count;
+            }
+            KeyCode::Char('G') => self.select(count.map_or(usize::MAX, |n| n.saturating_sub(1))),
+            KeyCode::Home => self.select(0),
+            KeyCode::End => self.select(usize::MAX),
+            _ => {}
+        }
+        GrepAction::None
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.hits.len().saturating_sub(1));
+    }
+
+    fn move_down(&mut self, rows: usize) {
+        self.select(self.selected.saturating_add(rows));
+    }
+
+    fn move_up(&mut self, rows: usize) {
+        self.selected = self.selected.saturating_sub(rows);
+    }
+}
+
+// .gitignoreを考慮してファイルをたどり、一致した行を送る。受け取る側がいなくなったら止める
+fn search_files(root: &Path, regex: &Regex, markdown_only: bool, tx: Sender<GrepProgress>) {
+    let walker = WalkBuilder::new(root)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    let mut files = 0;
+    let mut found = 0;
+    for entry in walker.flatten() {
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|file_type| file_type.is_file())
+            || (markdown_only && !is_markdown(path))
+            || entry.metadata().is_ok_and(|metadata| metadata.len() > LARGE_FILE_BYTES)
+        {
+            continue;
+        }
+        files += 1;
+        let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+        let mut hits = search_file(path, &relative, regex);
+        hits.truncate(MAX_HITS - found);
+        found += hits.len();
+        if (!hits.is_empty() || files % PROGRESS_FILES == 0)
+            && tx.send(GrepProgress { files, hits }).is_err()
+        {
+            return;
+        }
+        if found == MAX_HITS {
+            return;
+        }
+    }
+    let _ = tx.send(GrepProgress { files, hits: Vec::new() });
+}
+
+// 1つのファイルの中の一致した行を集める（読めないファイルやバイナリファイルは飛ばす）
+fn search_file(path: &Path, relative: &str, regex: &Regex) -> Vec<GrepHit> {
+    let Ok(bytes) = fs::read(path) else {
+        return Vec::new();
+    };
+    if is_binary(&bytes) {
+        return Vec::new();
+    }
+    let text = decode(&bytes).text;
+    let lines: Vec<&str> = text.lines().collect();
+    let mut found = 0;
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            // 空文字列への一致は強調表示できないので除く（プレビューの検索と同じ）
+            let ranges: Vec<(usize, usize)> = regex
+                .find_iter(line)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect();
+            if ranges.is_empty() {
+                return None;
+            }
+            found += ranges.len();
+            Some(GrepHit {
+                path: path.to_path_buf(),
+                relative: relative.to_string(),
+                line: i,
+                text: line.to_string(),
+                nth: found - ranges.len(),
+                ranges,
+                before: lines[i.saturating_sub(CONTEXT_LINES)..i]
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect(),
+                after: lines[i + 1..(i + 1 + CONTEXT_LINES).min(lines.len())]
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,406 @@
+// --- バイナリファイルの16進ダンプ表示 ---
+//
+// テキストとして読めないファイルは、位置・16進数・ASCIIの3列で表示する。
+// ファイル全体は読み込まず、画面に表示している範囲だけをその都度読む。
+// バイト列の検索も少しずつ読みながら行うので、数GBのファイルでも扱える。
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+
+use crate::ColorScheme;
+
+/// 1行に表示するバイト数
+const BYTES_PER_ROW: u64 = 16;
+
+/// 検索で一度に読むバイト数
+const SEARCH_CHUNK: u64 = 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HexPrompt {
+    Offset, // ":"で移動先の位置を入力中
+    Search, // "/"で検索するバイト列を入力中
+}
+
+pub struct HexView {
+    pub title: String,
+    file: File,
+    pub len: u64,                  // ファイルのバイト数
+    top_row: u64,                  // 先頭に表示している行
+    viewport_rows: usize,          // 画面に表示できる行数（描画時に更新）
+    window: Vec<u8>,               // 表示中の範囲のバイト列
+    window_start: Option<u64>,     // windowを読んだ位置（同じ範囲なら読み直さない）
+    pub prompt: Option<(HexPrompt, String)>,
+    pattern: Vec<u8>,              // 検索中のバイト列
+    pub query: String,             // 検索語（入力したまま）
+    highlight: Option<(u64, u64)>, // 強調表示する範囲（位置, バイト数）
+    pub status_message: Option<String>,
+    pending_count: Option<u64>,    // "20j"などの回数指定
+    pending_g: bool,               // "gg"の1文字目を入力済み
+}
+
+impl HexView {
+    pub fn open(file_path: &Path) -> io::Result<Self> {
+        let file = File::open(file_path)?;
+        let len = file.metadata()?.len();
+        let title = file_path
+            .file_name()
+            .map_or_else(|| file_path.to_string_lossy(), |s| s.to_string_lossy())
+            .to_string();
+        Ok(Self {
+            title,
+            file,
+            len,
+            top_row: 0,
+            viewport_rows: 1,
+            window: Vec::new(),
+            window_start: None,
+            prompt: None,
+            pattern: Vec::new(),
+            query: String::new(),
+            highlight: None,
+            status_message: None,
+            pending_count: None,
+            pending_g: false,
+        })
+    }
+
+    fn total_rows(&self) -> u64 {
+        self.len.div_ceil(BYTES_PER_ROW).max(1)
+    }
+
+    fn max_top_row(&self) -> u64 {
+        self.total_rows().saturating_sub(self.viewport_rows as u64)
+    }
+
+    /// 表示できる行数を設定し、表示する範囲を読み込む
+    pub fn set_viewport(&mut self, rows: u16) {
+        self.viewport_rows = rows.max(1) as usize;
+        self.top_row = self.top_row.min(self.max_top_row());
+
+        let start = self.top_row * BYTES_PER_ROW;
+        let size = self.viewport_rows as u64 * BYTES_PER_ROW;
+        let expected = size.min(self.len.saturating_sub(start));
+        if self.window_start == Some(start) && self.window.len() as u64 == expected {
+            return;
+        }
+        self.window.clear();
+        self.window_start = Some(start);
+        let result = self
+            .file
+            .seek(SeekFrom::Start(start))
+            .and_then(|_| (&mut self.file).take(size).read_to_end(&mut self.window));
+        if let Err(e) = result {
+            self.status_message = Some(format!("Read failed: {}", e));
+        }
+    }
+
+    /// フッターに表示する位置（例: "0x00001f40/0x0012ab34 45%"）
+    pub fn position_label(&self) -> String {
+        let visible_end = (self.top_row + self.viewport_rows as u64) * BYTES_PER_ROW;
+        let percent = (visible_end.min(self.len) * 100).checked_div(self.len).unwrap_or(100);
+        format!(
+            "0x{:08x}/0x{:08x} {}%",
+            self.top_row * BYTES_PER_ROW,
+            self.len,
+            percent
+        )
+    }
+
+    /// 入力途中の回数指定などの表示（例: "20"、"g"）
+    pub fn pending_keys(&self) -> String {
+        let mut keys = self.pending_count.map(|n| n.to_string()).unwrap_or_default();
+        if self.pending_g {
+            keys.push('g');
+        }
+        keys
+    }
+
+    pub fn is_prompting(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    fn scroll_up(&mut self, rows: u64) {
+        self.top_row = self.top_row.saturating_sub(rows);
+    }
+
+    fn scroll_down(&mut self, rows: u64) {
+        self.top_row = self.top_row.saturating_add(rows).min(self.max_top_row());
+    }
+
+    // 指定した位置が画面外なら、その位置の行を先頭に表示する
+    fn scroll_to_offset(&mut self, offset: u64) {
+        let row = offset / BYTES_PER_ROW;
+        if row < self.top_row || row >= self.top_row + self.viewport_rows as u64 {
+            self.top_row = row.min(self.max_top_row());
+        }
+    }
+
+    /// 16進ダンプ画面のキー操作（閉じる操作は呼び出し側で扱う）
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        self.status_message = None; // 操作時に一時メッセージを消す
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key.code);
+            return;
+        }
+
+        // 数字は次の移動の回数として溜めておく（先頭の0は回数にしない）
+        if let KeyCode::Char(c @ '0'..='9') = key.code
+            && (c != '0' || self.pending_count.is_some())
+        {
+            let digit = c.to_digit(10).unwrap_or(0) as u64;
+            let count = self.pending_count.unwrap_or(0);
+            self.pending_count = Some(count.saturating_mul(10).saturating_add(digit));
+            return;
+        }
+        let count = self.pending_count.take();
+        let times = count.unwrap_or(1);
+        let pending_g = std::mem::take(&mut self.pending_g);
+        let rows = self.viewport_rows as u64;
+        let half_page = (rows / 2).max(1);
+        let page = rows.saturating_sub(2).max(1); // 前後の2行は重ねて表示する
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Char('d') if ctrl => self.scroll_down(half_page * times),
+            KeyCode::Char('u') if ctrl => self.scroll_up(half_page * times),
+            KeyCode::Char('f') if ctrl => self.scroll_down(page * times),
+            KeyCode::Char('b') if ctrl => self.scroll_up(page * times),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_down(page * times),
+            KeyCode::PageUp => self.scroll_up(page * times),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_up(times),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_down(times),
+            // "gg"・"G"は先頭・末尾へ、回数指定があればその行へ移動する
+            KeyCode::Char('g') if pending_g => self.top_row = (times - 1).min(self.max_top_row()),
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+                self.pending_count = count;
+            }
+            KeyCode::Char('G') => match count {
+                Some(row) => self.top_row = row.saturating_sub(1).min(self.max_top_row()),
+                None => self.top_row = self.max_top_row(),
+            },
+            KeyCode::Home => self.top_row = 0,
+            KeyCode::End => self.top_row = self.max_top_row(),
+            KeyCode::Char(':') => self.prompt = Some((HexPrompt::Offset, String::new())),
+            KeyCode::Char('/') => self.prompt = Some((HexPrompt::Search, String::new())),
+            KeyCode::Char('n') => self.search_next(true),
+            KeyCode::Char('N') => self.search_next(false),
+            KeyCode::Esc => self.highlight = None, // 強調表示を消す
+            _ => {}
+        }
+    }
+
+    // 位置・検索語の入力中のキー操作
+    fn handle_prompt_key(&mut self, code: KeyCode) {
+        let Some((kind, input)) = &mut self.prompt else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                let kind = *kind;
+                let input = std::mem::take(input);
+                self.prompt = None;
+                match kind {
+                    HexPrompt::Offset => self.jump_to(&input),
+                    HexPrompt::Search => self.start_search(&input),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // ":"で入力した位置へ移動する
+    fn jump_to(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+        match parse_offset(input) {
+            Some(offset) if offset < self.len => {
+                self.highlight = Some((offset, 1));
+                self.scroll_to_offset(offset);
+            }
+            Some(_) => {
+                self.status_message = Some(format!("Offset out of range: {} (size 0x{:x})", input.trim(), self.len));
+            }
+            None => self.status_message = Some(format!("Invalid offset: {}", input.trim())),
+        }
+    }
+
+    // "/"で入力したバイト列を、表示中の先頭から探す
+    fn start_search(&mut self, input: &str) {
+        let Some(pattern) = parse_pattern(input) else {
+            return;
+        };
+        self.pattern = pattern;
+        self.query = input.to_string();
+        self.highlight = None;
+        self.search_from(self.top_row * BYTES_PER_ROW, true);
+    }
+
+    // 前回の一致箇所の次（前）から探す
+    fn search_next(&mut self, forward: bool) {
+        if self.pattern.is_empty() {
+            return;
+        }
+        let from = match self.highlight {
+            Some((offset, _)) if forward => offset + 1,
+            Some((offset, _)) => offset,
+            None => self.top_row * BYTES_PER_ROW,
+        };
+        self.search_from(from, forward);
+    }
+
+    // `from`から後ろ（前）へ探し、見つからなければファイルの反対側から探し直す
+    fn search_from(&mut self, from: u64, forward: bool) {
+        let from = from.min(self.len);
+        let (first, wrapped) = if forward {
+            ((from, self.len), (0, from))
+        } else {
+            ((0, from), (from, self.len))
+        };
+        let result = self.find(first.0, first.1, forward).and_then(|found| match found {
+            Some(offset) => Ok(Some((offset, false))),
+            None => Ok(self.find(wrapped.0, wrapped.1, forward)?.map(|offset| (offset, true))),
+        });
+        match result {
+            Ok(Some((offset, wrapped))) => {
+                self.highlight = Some((offset, self.pattern.len() as u64));
+                self.scroll_to_offset(offset);
+                self.status_message = Some(format!(
+                    "{}Found at 0x{:08x}",
+                    if wrapped { "Search wrapped | " } else { "" },
+                    offset
+                ));
+            }
+            Ok(None) => self.status_message = Some(format!("Pattern not found: {}", self.query)),
+            Err(e) => self.status_message = Some(format!("Search failed: {}", e)),
+        }
+    }
+
+    // [start, end)に始まる一致を、少しずつ読みながら探す。
+    // 前へ探すときは最初の、後ろへ探すときは最後の一致を返す
+    fn find(&mut self, start: u64, end: u64, forward: bool) -> io::Result<Option<u64>> {
+        let overlap = self.pattern.len() as u64 - 1; // 区切りをまたぐ一致のために多めに読む
+        let mut buffer = Vec::new();
+        let (mut lo, mut hi) = if forward {
+            (start, (start + SEARCH_CHUNK).min(end))
+        } else {
+            (end.saturating_sub(SEARCH_CHUNK).max(start), end)
+        };
+        while lo < hi {
+            buffer.clear();
+            self.file.seek(SeekFrom::Start(lo))?;
+            (&mut self.file).take(hi - lo + overlap).read_to_end(&mut buffer)?;
+            let mut matches = buffer
+                .windows(self.pattern.len())
+                .enumerate()
+                .filter(|(_, window)| *window == self.pattern.as_slice())
+                .map(|(i, _)| lo + i as u64)
+                .take_while(|&offset| offset < hi);
+            let found = if forward { matches.next() } else { matches.last() };
+            if found.is_some() {
+                return Ok(found);
+            }
+            (lo, hi) = if forward {
+                (hi, (hi + SEARCH_CHUNK).min(end))
+            } else {
+                (lo.saturating_sub(SEARCH_CHUNK).max(start), lo)
+            };
+        }
+        Ok(None)
+    }
+
+    /// 表示中の範囲を"位置  16進数  |ASCII|"の行にする
+    pub fn visible_lines(&self, theme: &ColorScheme) -> Vec<Line<'static>> {
+        let start = self.window_start.unwrap_or(0);
+        // 4GBを超えるファイルでは位置の桁を増やす
+        let digits = format!("{:x}", self.len).len().max(8);
+        self.window
+            .chunks(BYTES_PER_ROW as usize)
+            .enumerate()
+            .map(|(row, bytes)| {
+                let row_offset = start + row as u64 * BYTES_PER_ROW;
+                let mut spans = vec![Span::styled(
+                    format!("{:0width$x}  ", row_offset, width = digits),
+                    Style::default().fg(theme.comment),
+                )];
+                for i in 0..BYTES_PER_ROW as usize {
+                    let separator = if i == 7 { "  " } else { " " };
+                    match bytes.get(i) {
+                        Some(&byte) => {
+                            let style = self.byte_style(row_offset + i as u64, byte, theme);
+                            spans.push(Span::styled(format!("{:02x}", byte), style));
+                        }
+                        None => spans.push(Span::raw("  ")),
+                    }
+                    spans.push(Span::raw(separator));
+                }
+                spans.push(Span::styled(" |", Style::default().fg(theme.comment)));
+                for (i, &byte) in bytes.iter().enumerate() {
+                    let style = self.byte_style(row_offset + i as u64, byte, theme);
+                    let c = if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' };
+                    spans.push(Span::styled(c.to_string(), style));
+                }
+                spans.push(Span::styled("|", Style::default().fg(theme.comment)));
+                Line::from(spans)
+            })
+            .collect()
+    }
+
+    // 強調表示の範囲、NUL、ASCIIの文字、それ以外のバイトで色を分ける
+    fn byte_style(&self, offset: u64, byte: u8, theme: &ColorScheme) -> Style {
+        let highlighted = self
+            .highlight
+            .is_some_and(|(start, len)| offset >= start && offset < start + len);
+        if highlighted {
+            Style::default().fg(theme.bg).bg(theme.current_match_bg)
+        } else if byte == 0 {
+            Style::default().fg(theme.comment)
+        } else if byte.is_ascii_graphic() || byte == b' ' {
+            Style::default().fg(theme.fg)
+        } else {
+            Style::default().fg(theme.code_fg)
+        }
+    }
+}
+
+/// "0x1f40"（16進数）または"8000"（10進数）の位置
+fn parse_offset(input: &str) -> Option<u64> {
+    let input = input.trim().replace('_', "");
+    match input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => input.parse().ok(),
+    }
+}
+
+/// 検索するバイト列。"89 50 4e 47"のような16進数の並びはバイト列として、
+/// それ以外（または"..."で囲んだもの）は文字列のUTF-8として探す
+fn parse_pattern(input: &str) -> Option<Vec<u8>> {
+    if input.is_empty() {
+        return None;
+    }
+    if let Some(text) = input.strip_prefix('"').and_then(|s| s.strip_suffix('"'))
+        && !text.is_empty()
+    {
+        return Some(text.as_bytes().to_vec());
+    }
+    let hex = input.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    if !hex.is_empty() && hex.len() % 2 == 0 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect();
+    }
+    Some(input.as_bytes().to_vec())
+}
@@ -0,0 +1,90 @@
+// --- リンクのヒント ---
+//
+// プレビュー中のリンクに"a"・"s"…のような短いラベルを付け、ラベルを入力して選ぶ。
+// リンクが多いときは2文字のラベルにし、1文字目を入力した時点で候補を絞り込む。
+
+use std::iter;
+
+use crossterm::event::KeyCode;
+
+/// ラベルに使う文字（ホームポジションから順に。スクロールのj・kは除く）
+const LABEL_CHARS: &str = "asdfghlqwertyuiopzxcvbnm";
+
+pub struct LinkHints {
+    pub labels: Vec<String>, // リンクごとのラベル（リンクの順）
+    pub typed: String,       // 入力済みのラベルの文字
+    pub selected: usize,     // 矢印キーで選択中のリンク
+}
+
+pub enum HintAction {
+    None,
+    Cancel,
+    Follow(usize), // 選んだリンクの添字
+}
+
+impl LinkHints {
+    pub fn new(count: usize, selected: usize) -> Self {
+        Self {
+            labels: labels(count),
+            typed: String::new(),
+            selected,
+        }
+    }
+
+    /// 入力済みの文字で始まるラベルのリンク
+    pub fn matching(&self) -> Vec<usize> {
+        (0..self.labels.len())
+            .filter(|&i| self.labels[i].starts_with(&self.typed))
+            .collect()
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> HintAction {
+        match code {
+            KeyCode::Esc => HintAction::Cancel,
+            KeyCode::Enter => HintAction::Follow(self.selected),
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.labels.len().saturating_sub(1));
+                HintAction::None
+            }
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                HintAction::None
+            }
+            KeyCode::Backspace => {
+                self.typed.pop();
+                HintAction::None
+            }
+            KeyCode::Char(c) if LABEL_CHARS.contains(c) => {
+                self.typed.push(c);
+                let matching = self.matching();
+                match matching.as_slice() {
+                    [] => {
+                        self.typed.pop(); // どのラベルにも当たらない文字は無視する
+                        HintAction::None
+                    }
+                    [i] if self.labels[*i] == self.typed => HintAction::Follow(*i),
+                    [first, ..] => {
+                        self.selected = *first;
+                        HintAction::None
+                    }
+                }
+            }
+            _ => HintAction::None,
+        }
+    }
+}
+
+// 1文字で足りなければすべて2文字にする（1文字のラベルが2文字のラベルの先頭と重ならないように）。
+// 2文字でも足りない分はラベルなし（矢印キーとEnterで選ぶ）
+fn labels(count: usize) -> Vec<String> {
+    let chars = LABEL_CHARS.chars().collect::<Vec<_>>();
+    let labels: Vec<String> = if count <= chars.len() {
+        chars.iter().map(|c| c.to_string()).collect()
+    } else {
+        chars
+            .iter()
+            .flat_map(|&first| chars.iter().map(move |&second| format!("{}{}", first, second)))
+            .collect()
+    };
+    labels.into_iter().chain(iter::repeat(String::new())).take(count).collect()
+}
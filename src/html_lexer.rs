@@ -0,0 +1,409 @@
+// --- HTMLの字句解析とハイライト ---
+//
+// 行をまたぐタグや属性、コメントを正しく扱うため、状態を保ったまま
+// ソース全体を1回走査してトークンに分割する。
+
+use ratatui::prelude::*;
+
+use crate::{
+    syntax::{highlight_lines, syntax_for_lang, Syntax},
+    ColorScheme,
+};
+
+/// トークンの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Text,
+    Punct, // < </ > /> = などの記号
+    TagName,
+    AttrName,
+    AttrValue,
+    Entity,
+    Comment,
+    Doctype,
+    RawText, // <script>・<style>の中身
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Data,
+    TagName { closing: bool },
+    InTag,
+    BeforeValue,
+    RawText(&'static str),
+}
+
+// 中身をHTMLとして解釈しない要素
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+pub struct HtmlLexer<'a> {
+    src: &'a str,
+    pos: usize,
+    state: State,
+    raw_element: Option<&'static str>, // 直前に開いたタグがscript/styleならその名前
+}
+
+impl<'a> HtmlLexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            state: State::Data,
+            raw_element: None,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    // 現在位置から`len`バイトを切り出してトークンにする
+    fn take(&mut self, kind: TokenKind, len: usize) -> Token<'a> {
+        let text = &self.src[self.pos..self.pos + len];
+        self.pos += len;
+        Token { kind, text }
+    }
+
+    // `pred`を満たす文字が続く長さ（バイト数）
+    fn span_while(&self, pred: impl Fn(char) -> bool) -> usize {
+        self.rest()
+            .char_indices()
+            .find(|&(_, c)| !pred(c))
+            .map_or(self.rest().len(), |(i, _)| i)
+    }
+
+    // `terminator`までを含む長さ。見つからなければ末尾まで
+    fn span_through(&self, terminator: &str) -> usize {
+        self.rest()
+            .find(terminator)
+            .map_or(self.rest().len(), |i| i + terminator.len())
+    }
+
+    fn lex_data(&mut self) -> Token<'a> {
+        let rest = self.rest();
+        if rest.starts_with("<!--") {
+            let len = self.span_through("-->");
+            return self.take(TokenKind::Comment, len);
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            let len = self.span_through(">");
+            return self.take(TokenKind::Doctype, len);
+        }
+        if let Some(after) = rest.strip_prefix("</")
+            && after.starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            self.state = State::TagName { closing: true };
+            return self.take(TokenKind::Punct, 2);
+        }
+        if let Some(after) = rest.strip_prefix('<')
+            && after.starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            self.state = State::TagName { closing: false };
+            return self.take(TokenKind::Punct, 1);
+        }
+        if let Some(name) = rest.strip_prefix('&') {
+            let name_len = name
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+                .unwrap_or(name.len());
+            if name_len > 0 && name[name_len..].starts_with(';') {
+                return self.take(TokenKind::Entity, name_len + 2);
+            }
+        }
+        // 次のタグか文字参照の手前までは地の文
+        let first = rest.chars().next().map_or(1, char::len_utf8);
+        let len = rest[first..]
+            .find(['<', '&'])
+            .map_or(rest.len(), |i| i + first);
+        self.take(TokenKind::Text, len)
+    }
+
+    fn lex_tag_name(&mut self, closing: bool) -> Token<'a> {
+        let len = self.span_while(|c| !(c.is_whitespace() || c == '/' || c == '>'));
+        let token = self.take(TokenKind::TagName, len);
+        self.raw_element = if closing {
+            None
+        } else {
+            RAW_TEXT_ELEMENTS
+                .into_iter()
+                .find(|name| token.text.eq_ignore_ascii_case(name))
+        };
+        self.state = State::InTag;
+        token
+    }
+
+    fn lex_in_tag(&mut self) -> Token<'a> {
+        let rest = self.rest();
+        if rest.starts_with(char::is_whitespace) {
+            let len = self.span_while(char::is_whitespace);
+            return self.take(TokenKind::Text, len);
+        }
+        if rest.starts_with("/>") {
+            self.state = State::Data;
+            self.raw_element = None;
+            return self.take(TokenKind::Punct, 2);
+        }
+        if rest.starts_with('>') {
+            self.state = match self.raw_element.take() {
+                Some(name) => State::RawText(name),
+                None => State::Data,
+            };
+            return self.take(TokenKind::Punct, 1);
+        }
+        if rest.starts_with('=') {
+            self.state = State::BeforeValue;
+            return self.take(TokenKind::Punct, 1);
+        }
+        if rest.starts_with('/') {
+            return self.take(TokenKind::Punct, 1);
+        }
+        let len = self.span_while(|c| !(c.is_whitespace() || c == '/' || c == '>' || c == '='));
+        self.take(TokenKind::AttrName, len)
+    }
+
+    fn lex_before_value(&mut self) -> Token<'a> {
+        let rest = self.rest();
+        if rest.starts_with(char::is_whitespace) {
+            let len = self.span_while(char::is_whitespace);
+            return self.take(TokenKind::Text, len);
+        }
+        self.state = State::InTag;
+        if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            // 引用符付きの値は改行を含んでいても閉じ引用符まで続く
+            let len = rest[1..].find(quote).map_or(rest.len(), |i| i + 2);
+            return self.take(TokenKind::AttrValue, len);
+        }
+        if rest.starts_with('>') {
+            return self.lex_in_tag();
+        }
+        let len = self.span_while(|c| !(c.is_whitespace() || c == '>'));
+        self.take(TokenKind::AttrValue, len)
+    }
+
+    fn lex_raw_text(&mut self, element: &'static str) -> Token<'a> {
+        self.state = State::Data;
+        let rest = self.rest();
+        let end = rest
+            .match_indices("</")
+            .map(|(i, _)| i)
+            .find(|&i| {
+                rest[i + 2..]
+                    .get(..element.len())
+                    .is_some_and(|name| name.eq_ignore_ascii_case(element))
+            })
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return self.lex_data();
+        }
+        self.take(TokenKind::RawText, end)
+    }
+}
+
+impl<'a> Iterator for HtmlLexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.pos >= self.src.len() {
+            return None;
+        }
+        let token = match self.state {
+            State::Data => self.lex_data(),
+            State::TagName { closing } => self.lex_tag_name(closing),
+            State::InTag => self.lex_in_tag(),
+            State::BeforeValue => self.lex_before_value(),
+            State::RawText(element) => self.lex_raw_text(element),
+        };
+        Some(token)
+    }
+}
+
+fn token_style(kind: TokenKind, theme: &ColorScheme) -> Style {
+    let style = Style::default();
+    match kind {
+        TokenKind::Text | TokenKind::RawText => style.fg(theme.fg),
+        TokenKind::Punct => style.fg(theme.comment),
+        TokenKind::TagName => style.fg(theme.html_tag),
+        TokenKind::AttrName => style.fg(theme.html_attr),
+        TokenKind::AttrValue => style.fg(theme.html_value),
+        TokenKind::Entity => style.fg(theme.html_entity),
+        TokenKind::Comment => style.fg(theme.comment).add_modifier(Modifier::ITALIC),
+        TokenKind::Doctype => style.fg(theme.html_doctype),
+    }
+}
+
+// 複数行にまたがるトークンを行ごとに分割しながらテキストを組み立てる
+#[derive(Default)]
+struct TextWriter {
+    lines: Vec<Line<'static>>,
+    spans: Vec<Span<'static>>,
+}
+
+impl TextWriter {
+    fn push(&mut self, text: &str, style: Style) {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.lines.push(Line::from(std::mem::take(&mut self.spans)));
+            }
+            let part = part.strip_suffix('\r').unwrap_or(part);
+            if !part.is_empty() {
+                self.spans.push(Span::styled(part.to_string(), style));
+            }
+        }
+    }
+
+    fn finish(mut self) -> Text<'static> {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(self.spans));
+        }
+        Text::from(self.lines)
+    }
+}
+
+/// HTMLソースをトークンごとに色分けしたテキストにする。
+/// `<code class="language-xxx">`と`<script>`・`<style>`の中身は言語に応じてハイライトする
+pub fn highlight_html(html_source: &str, theme: &ColorScheme) -> Text<'static> {
+    let tokens = HtmlLexer::new(html_source).collect::<Vec<_>>();
+    let mut writer = TextWriter::default();
+    let mut tag_name = String::new();
+    let mut attr_name = String::new();
+    let mut code_lang: Option<String> = None;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        i += 1;
+        match token.kind {
+            TokenKind::TagName => {
+                tag_name = token.text.to_ascii_lowercase();
+                code_lang = None;
+            }
+            TokenKind::AttrName => attr_name = token.text.to_ascii_lowercase(),
+            TokenKind::AttrValue if tag_name == "code" && attr_name == "class" => {
+                code_lang = token
+                    .text
+                    .trim_matches(['"', '\''])
+                    .split_whitespace()
+                    .find_map(|class| class.strip_prefix("language-"))
+                    .map(str::to_string);
+            }
+            TokenKind::RawText => {
+                let lang = if tag_name == "style" { "css" } else { "js" };
+                if let Some(syntax) = syntax_for_lang(lang) {
+                    write_highlighted(&mut writer, token.text, syntax, theme);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        writer.push(token.text, token_style(token.kind, theme));
+
+        // コードブロックの開始タグを閉じたら、終了タグまでの本文をまとめてハイライトする
+        let opens_code = token.kind == TokenKind::Punct && token.text == ">";
+        if opens_code
+            && let Some(syntax) = code_lang.take().and_then(|lang| syntax_for_lang(&lang))
+        {
+            let body_len = tokens[i..]
+                .iter()
+                .take_while(|t| matches!(t.kind, TokenKind::Text | TokenKind::Entity))
+                .count();
+            let body = tokens[i..i + body_len]
+                .iter()
+                .map(|t| t.text)
+                .collect::<String>();
+            write_escaped_code(&mut writer, &body, syntax, theme);
+            i += body_len;
+        }
+    }
+
+    writer.finish()
+}
+
+// エスケープされていないコード（<script>・<style>の中身）をハイライトする
+fn write_highlighted(writer: &mut TextWriter, code: &str, syntax: Syntax, theme: &ColorScheme) {
+    let fallback = Style::default().fg(theme.fg);
+    let lines = highlight_lines(code, syntax, fallback);
+    let line_count = lines.len();
+    for (i, line) in lines.into_iter().enumerate() {
+        for span in line.spans {
+            writer.push(&span.content, span.style);
+        }
+        if i + 1 < line_count || code.ends_with('\n') {
+            writer.push("\n", fallback);
+        }
+    }
+}
+
+// HTMLエスケープされたコードは、元に戻してからハイライトし、
+// その色をエスケープされたままの表記に当てはめて表示する
+fn write_escaped_code(writer: &mut TextWriter, escaped: &str, syntax: Syntax, theme: &ColorScheme) {
+    let fallback = Style::default().fg(theme.fg);
+
+    // 表示上の断片と、それが元のコードで何文字に当たるか
+    let mut pieces = Vec::new();
+    let mut code = String::new();
+    let mut rest = escaped;
+    while let Some(c) = rest.chars().next() {
+        let (piece_len, decoded) = match decode_entity(rest) {
+            Some((len, decoded)) => (len, decoded),
+            None => (c.len_utf8(), c),
+        };
+        pieces.push(&rest[..piece_len]);
+        code.push(decoded);
+        rest = &rest[piece_len..];
+    }
+
+    // 元のコード1文字ごとのスタイル（改行も1文字として数える）
+    let mut char_styles = Vec::new();
+    for line in highlight_lines(&code, syntax, fallback) {
+        for span in &line.spans {
+            char_styles.extend(span.content.chars().map(|_| span.style));
+        }
+        char_styles.push(fallback);
+    }
+
+    let mut pending = String::new();
+    let mut pending_style = fallback;
+    for (piece, style) in pieces
+        .into_iter()
+        .zip(char_styles.into_iter().chain(std::iter::repeat(fallback)))
+    {
+        if style != pending_style && !pending.is_empty() {
+            writer.push(&pending, pending_style);
+            pending.clear();
+        }
+        pending_style = style;
+        pending.push_str(piece);
+    }
+    if !pending.is_empty() {
+        writer.push(&pending, pending_style);
+    }
+}
+
+// 先頭が文字参照なら、その長さ（バイト数）と表す文字を返す
+fn decode_entity(text: &str) -> Option<(usize, char)> {
+    let body = text.strip_prefix('&')?;
+    let end = body.find(';').filter(|&end| end > 0 && end <= 10)?;
+    let name = &body[..end];
+    let decoded = match name {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let value = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(value)?
+        }
+    };
+    Some((end + 2, decoded))
+}
@@ -0,0 +1,738 @@
+// --- 大きなファイルのプレビュー ---
+//
+// 数百MBのログなどは、全体を読み込んで色付け・折り返しをすると時間もメモリもかかる。
+// そこで各行の開始位置だけを索引にしておき、画面に表示する行だけをその都度読んで整形する。
+// 索引は別のスレッドで作るので、開いてすぐに先頭から読み始められる。
+// 検索も別のスレッドでファイルを順に読んで行うので、探している間も操作できる。
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use ratatui::prelude::*;
+use regex::Regex;
+
+use crate::ColorScheme;
+use crate::encoding::{decode, is_binary};
+use crate::search::{Search, restyle_range};
+use crate::wrap::wrap_text;
+
+/// これより大きなファイルは、全体を読み込まずにこの表示で開く
+pub const LARGE_FILE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// 索引を作るときに一度に読むバイト数
+const INDEX_CHUNK: usize = 1024 * 1024;
+
+/// 1行のうち表示・検索するバイト数（改行のない巨大なファイルに備える）
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+/// 文字コードの判定に使う先頭のバイト数
+const SNIFF_BYTES: u64 = 8 * 1024;
+
+/// 検索の進み具合を調べる間隔（行数）
+const PROGRESS_LINES: usize = 4096;
+
+// 索引が届くのを待っている移動
+#[derive(Clone, Copy)]
+enum Jump {
+    Line(usize), // その行（0始まり）を先頭に表示する
+    End,         // 末尾を表示する
+}
+
+// 検索スレッドから送る結果
+enum SearchEvent {
+    Progress(u64), // 読み終えたバイト数
+    Done(io::Result<Option<SearchHit>>),
+}
+
+// 見つかった一致箇所（行, 行の中のバイト位置）と、ファイルの反対側から探し直したかどうか
+type SearchHit = ((usize, usize), bool);
+
+// 別スレッドで行う検索の条件
+struct SearchJob {
+    path: PathBuf,
+    encoding: &'static Encoding,
+    pattern: Regex,
+    target: (usize, usize), // この位置以降（後ろへ探すときはより前）の一致を探す
+    first_line: usize,      // 前へ探すときに読み始める行（索引にある行）
+    offset: u64,            // first_lineの開始位置
+    forward: bool,
+}
+
+// 表示中の行を読んだ結果
+struct Window {
+    top_line: usize,
+    lines: Vec<String>,
+}
+
+pub struct LargeFileView {
+    pub title: String,
+    path: PathBuf,
+    file: File,
+    pub len: u64, // ファイルのバイト数
+    encoding: &'static Encoding,
+    pub encoding_label: String,
+    line_starts: Vec<u64>,                         // 各行の開始位置（索引を作った分だけ）
+    indexer: Option<Receiver<io::Result<Vec<u64>>>>, // 索引を作っている間だけSome
+    top_line: usize,                               // 先頭に表示している行
+    width: u16,
+    height: u16,
+    window: Option<Window>,
+    bottom: Option<((usize, u16, u16), usize)>, // 末尾を表示するときの先頭の行（行数・幅・高さごと）
+    pub search: Search,                         // 検索語と検索モード（一致箇所は表示中の行だけ探す）
+    pattern: Option<Regex>,
+    current_match: Option<(usize, usize)>, // 選択中の一致箇所（行, 行の中のバイト位置）
+    pub search_prompt: Option<String>,     // 検索語の入力中（入力前の検索語を保持）
+    pub line_prompt: Option<String>,
+    pub status_message: Option<String>,
+    pending_count: Option<usize>, // "20j"などの回数指定
+    pending_g: bool,              // "gg"の1文字目を入力済み
+    pending_jump: Option<Jump>,   // 索引が届いたら続ける移動
+    searcher: Option<Receiver<SearchEvent>>, // 検索している間だけSome
+    searched_bytes: u64,                     // 検索で読み終えたバイト数
+}
+
+impl LargeFileView {
+    pub fn open(file_path: &Path) -> io::Result<Self> {
+        let mut file = File::open(file_path)?;
+        let len = file.metadata()?.len();
+        let mut head = Vec::new();
+        (&mut file).take(SNIFF_BYTES).read_to_end(&mut head)?;
+        if is_binary(&head) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "バイナリファイルのため表示できません",
+            ));
+        }
+        // 途中で切れた文字で判定を誤らないよう、最後の改行までで判定する。
+        // 行ごとに読むので、改行が1バイトではないUTF-16は扱えない
+        let complete = match head.iter().rposition(|&byte| byte == b'\n') {
+            Some(end) if len > head.len() as u64 => &head[..=end],
+            _ => &head,
+        };
+        let detected = decode(complete);
+        if detected.encoding == UTF_16LE || detected.encoding == UTF_16BE {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "UTF-16の大きなファイルは表示できません",
+            ));
+        }
+        let title = file_path
+            .file_name()
+            .map_or_else(|| file_path.to_string_lossy(), |s| s.to_string_lossy())
+            .to_string();
+        Ok(Self {
+            title,
+            path: file_path.to_path_buf(),
+            file,
+            len,
+            encoding: detected.encoding,
+            encoding_label: detected.encoding.name().to_string(),
+            line_starts: vec![0],
+            indexer: Some(spawn_indexer(file_path)?),
+            top_line: 0,
+            width: 1,
+            height: 1,
+            window: None,
+            bottom: None,
+            search: Search::default(),
+            pattern: None,
+            current_match: None,
+            search_prompt: None,
+            line_prompt: None,
+            status_message: None,
+            pending_count: None,
+            pending_g: false,
+            pending_jump: None,
+            searcher: None,
+            searched_bytes: 0,
+        })
+    }
+
+    /// 別スレッドで作った索引を取り込む
+    fn poll_index(&mut self) {
+        let Some(indexer) = &self.indexer else {
+            return;
+        };
+        let known_lines = self.known_lines();
+        let mut done = false;
+        loop {
+            match indexer.try_recv() {
+                Ok(Ok(starts)) => self.line_starts.extend(starts),
+                Ok(Err(e)) => {
+                    self.status_message = Some(format!("Indexing failed: {}", e));
+                    done = true;
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+        if done {
+            self.indexer = None;
+            // 末尾の改行の後ろは行として数えない
+            if self.line_starts.len() > 1 && self.line_starts.last() == Some(&self.len) {
+                self.line_starts.pop();
+            }
+        }
+        // 索引が伸びたら、表示しきれていなかった行を読み直す
+        let incomplete = self.window.as_ref().is_some_and(|window| window.lines.len() < self.height as usize);
+        if incomplete && self.known_lines() != known_lines {
+            self.window = None;
+        }
+        if self.known_lines() != known_lines || done {
+            self.apply_pending_jump();
+        }
+    }
+
+    fn is_indexing(&self) -> bool {
+        self.indexer.is_some()
+    }
+
+    // 表示できる行数。索引を作っている間は、終わりが分かっていない最後の行を除く
+    fn known_lines(&self) -> usize {
+        if self.is_indexing() {
+            self.line_starts.len() - 1
+        } else {
+            self.line_starts.len()
+        }
+    }
+
+    // 行のバイト範囲（改行を除く）
+    fn line_range(&self, line: usize) -> (u64, u64) {
+        let start = self.line_starts[line];
+        let end = self.line_starts.get(line + 1).map_or(self.len, |&next| next - 1);
+        (start, end.max(start))
+    }
+
+    // 1行を読んで文字列にする。長すぎる行は途中までにする
+    fn read_line(&mut self, line: usize) -> io::Result<String> {
+        let (start, end) = self.line_range(line);
+        let mut bytes = Vec::new();
+        self.file.seek(SeekFrom::Start(start))?;
+        (&mut self.file).take((end - start).min(MAX_LINE_BYTES)).read_to_end(&mut bytes)?;
+        let mut text = self.decode_line(line, &bytes);
+        if end - start > MAX_LINE_BYTES {
+            text.push_str(" …");
+        }
+        Ok(text)
+    }
+
+    fn decode_line(&self, line: usize, bytes: &[u8]) -> String {
+        decode_line(self.encoding, line, bytes)
+    }
+
+    /// 表示できる大きさを設定し、表示する行を読み込む
+    pub fn set_viewport(&mut self, width: u16, height: u16) {
+        self.poll_index();
+        self.poll_search();
+        let (width, height) = (width.max(1), height.max(1));
+        if (width, height) != (self.width, self.height) {
+            self.window = None;
+        }
+        self.width = width;
+        self.height = height;
+        self.top_line = self.top_line.min(self.max_top_line());
+        if self.window.as_ref().is_some_and(|window| window.top_line == self.top_line) {
+            return;
+        }
+
+        let end = (self.top_line + self.height as usize).min(self.known_lines());
+        let lines = (self.top_line..end).map(|line| self.read_line(line)).collect();
+        match lines {
+            Ok(lines) => {
+                self.window = Some(Window {
+                    top_line: self.top_line,
+                    lines,
+                })
+            }
+            Err(e) => self.status_message = Some(format!("Read failed: {}", e)),
+        }
+    }
+
+    // 末尾の行が画面の下端に来るときの先頭の行。折り返しを考えて末尾から数える
+    fn max_top_line(&mut self) -> usize {
+        let key = (self.known_lines(), self.width, self.height);
+        if let Some((cached, top_line)) = self.bottom
+            && cached == key
+        {
+            return top_line;
+        }
+        let mut rows = 0;
+        let mut top_line = self.known_lines();
+        while top_line > 0 {
+            let text = self.read_line(top_line - 1).unwrap_or_default();
+            rows += wrap_text(&Text::raw(text), self.width).rows.len();
+            if rows > self.height as usize {
+                break;
+            }
+            top_line -= 1;
+        }
+        self.bottom = Some((key, top_line));
+        top_line
+    }
+
+    /// 表示中の行を折り返し、検索の一致箇所を強調したもの
+    pub fn visible_text(&self, theme: &ColorScheme) -> Text<'static> {
+        let Some(window) = &self.window else {
+            return Text::default();
+        };
+        let match_style = Style::default().bg(theme.match_bg);
+        let current_style = Style::default()
+            .fg(theme.bg)
+            .bg(theme.current_match_bg)
+            .add_modifier(Modifier::BOLD);
+        let lines = window
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let line_index = window.top_line + i;
+                let mut line = Line::styled(text.clone(), Style::default().fg(theme.fg));
+                if let Some(pattern) = &self.pattern {
+                    for m in pattern.find_iter(text).filter(|m| !m.is_empty()) {
+                        let style = if self.current_match == Some((line_index, m.start())) {
+                            current_style
+                        } else {
+                            match_style
+                        };
+                        line = restyle_range(&line, m.start(), m.end(), style);
+                    }
+                }
+                line
+            })
+            .collect::<Vec<_>>();
+        let wrapped = wrap_text(&Text::from(lines), self.width);
+        Text::from(wrapped.rows.into_iter().take(self.height as usize).collect::<Vec<_>>())
+    }
+
+    /// フッターに表示する位置（例: "Ln 120/2000000 45%"、索引の作成中は"Ln 120/34000+"）
+    pub fn position_label(&self) -> String {
+        let offset = self.line_starts.get(self.top_line + self.height as usize).copied();
+        let percent = (offset.unwrap_or(self.len).min(self.len) * 100)
+            .checked_div(self.len)
+            .unwrap_or(100);
+        format!(
+            "Ln {}/{}{} {}%",
+            (self.top_line + 1).min(self.known_lines()),
+            self.known_lines(),
+            if self.is_indexing() { "+ (indexing)" } else { "" },
+            percent
+        )
+    }
+
+    /// 入力途中の回数指定などの表示（例: "20"、"g"）
+    pub fn pending_keys(&self) -> String {
+        let mut keys = self.pending_count.map(|n| n.to_string()).unwrap_or_default();
+        if self.pending_g {
+            keys.push('g');
+        }
+        keys
+    }
+
+    /// 検索中なら読み終えた割合（%）
+    pub fn search_progress(&self) -> Option<u64> {
+        self.searcher.as_ref().map(|_| {
+            (self.searched_bytes * 100)
+                .checked_div(self.len)
+                .unwrap_or(0)
+                .min(99)
+        })
+    }
+
+    pub fn is_prompting(&self) -> bool {
+        self.search_prompt.is_some() || self.line_prompt.is_some()
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        self.top_line = self.top_line.saturating_sub(lines);
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        self.top_line = self.top_line.saturating_add(lines).min(self.max_top_line());
+    }
+
+    // 指定した行が画面外なら、その行を先頭に表示する
+    fn scroll_to_line(&mut self, line: usize) {
+        if line < self.top_line || line >= self.top_line + self.height as usize {
+            self.top_line = line.min(self.max_top_line());
+        }
+    }
+
+    /// 大きなファイルの表示のキー操作（閉じる操作は呼び出し側で扱う）
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        self.status_message = None; // 操作時に一時メッセージを消す
+        if self.search_prompt.is_some() {
+            self.handle_search_key(key.code, key.modifiers);
+            return;
+        }
+        if self.line_prompt.is_some() {
+            self.handle_line_prompt_key(key.code);
+            return;
+        }
+
+        // 数字は次の移動の回数として溜めておく（先頭の0は回数にしない）
+        if let KeyCode::Char(c @ '0'..='9') = key.code
+            && (c != '0' || self.pending_count.is_some())
+        {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            let count = self.pending_count.unwrap_or(0);
+            self.pending_count = Some(count.saturating_mul(10).saturating_add(digit));
+            return;
+        }
+        let count = self.pending_count.take();
+        let times = count.unwrap_or(1);
+        let pending_g = std::mem::take(&mut self.pending_g);
+        self.pending_jump = None; // 別の操作をしたら、索引を待っている移動はやめる
+        let half_page = (self.height as usize / 2).max(1);
+        let page = (self.height as usize).saturating_sub(2).max(1); // 前後の2行は重ねて表示する
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Char('d') if ctrl => self.scroll_down(half_page * times),
+            KeyCode::Char('u') if ctrl => self.scroll_up(half_page * times),
+            KeyCode::Char('f') if ctrl => self.scroll_down(page * times),
+            KeyCode::Char('b') if ctrl => self.scroll_up(page * times),
+            KeyCode::PageDown => self.scroll_down(page * times),
+            KeyCode::PageUp => self.scroll_up(page * times),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_up(times),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_down(times),
+            // "gg"・"G"は先頭・末尾へ、回数指定があればその行へ移動する
+            KeyCode::Char('g') if pending_g => self.go_to_line(times),
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+                self.pending_count = count;
+            }
+            KeyCode::Char('G') => match count {
+                Some(line) => self.go_to_line(line),
+                None => self.go_to_end(),
+            },
+            KeyCode::Home => self.top_line = 0,
+            KeyCode::End => self.go_to_end(),
+            KeyCode::Char(':') => self.line_prompt = Some(String::new()),
+            KeyCode::Char('/') => self.search_prompt = Some(std::mem::take(&mut self.search.query)),
+            KeyCode::Char('n') => self.search_next(true),
+            KeyCode::Char('N') => self.search_next(false),
+            KeyCode::Esc => {
+                // 検索をやめ、強調表示を消す
+                self.search.query.clear();
+                self.pattern = None;
+                self.current_match = None;
+                self.searcher = None;
+            }
+            _ => {}
+        }
+    }
+
+    // 1始まりの行番号へ移動する
+    fn go_to_line(&mut self, line: usize) {
+        self.jump(Jump::Line(line.saturating_sub(1)));
+    }
+
+    fn go_to_end(&mut self) {
+        self.jump(Jump::End);
+    }
+
+    // 索引がまだ届いていなければ、索引のあるところまで移動し、索引が伸びるたびに移動し直す
+    fn jump(&mut self, jump: Jump) {
+        self.pending_jump = Some(jump);
+        self.apply_pending_jump();
+    }
+
+    fn apply_pending_jump(&mut self) {
+        let Some(jump) = self.pending_jump else {
+            return;
+        };
+        let reached = match jump {
+            Jump::Line(line) => {
+                self.top_line = line.min(self.max_top_line());
+                line < self.known_lines()
+            }
+            Jump::End => {
+                self.top_line = self.max_top_line();
+                false
+            }
+        };
+        if reached || !self.is_indexing() {
+            self.pending_jump = None;
+        }
+    }
+
+    // 行番号の入力中のキー操作
+    fn handle_line_prompt_key(&mut self, code: KeyCode) {
+        let Some(input) = &mut self.line_prompt else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let input = self.line_prompt.take().unwrap_or_default();
+                match input.trim().parse::<usize>() {
+                    Ok(line) => self.go_to_line(line),
+                    Err(_) if input.trim().is_empty() => {}
+                    Err(_) => self.status_message = Some(format!("Invalid line number: {}", input)),
+                }
+            }
+            KeyCode::Esc => self.line_prompt = None,
+            _ => {}
+        }
+    }
+
+    // 検索語の入力中のキー操作。ファイル全体を読むので、Enterを押してから検索する
+    fn handle_search_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            // Alt+r・Alt+cで正規表現・大文字小文字の区別を切り替える
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::ALT) => self.search.regex = !self.search.regex,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::ALT) => {
+                self.search.ignore_case = !self.search.ignore_case
+            }
+            KeyCode::Char(c) => self.search.query.push(c),
+            KeyCode::Backspace => {
+                self.search.query.pop();
+            }
+            KeyCode::Enter => {
+                self.search_prompt = None;
+                self.start_search();
+            }
+            KeyCode::Esc => {
+                // 入力前の検索語に戻す
+                if let Some(saved_query) = self.search_prompt.take() {
+                    self.search.query = saved_query;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn start_search(&mut self) {
+        self.current_match = None;
+        self.pattern = None;
+        if self.search.query.is_empty() {
+            return;
+        }
+        match self.search.build_regex() {
+            Ok(pattern) => self.pattern = Some(pattern),
+            Err(e) => {
+                let message = e.to_string();
+                let summary = message.lines().last().unwrap_or_default().trim();
+                self.status_message = Some(format!("Invalid pattern: {}", summary.trim_start_matches("error: ")));
+                return;
+            }
+        }
+        // 表示中の先頭の行から探す
+        self.find_from(self.top_line, 0, true);
+    }
+
+    // 前回の一致箇所の次（前）を探す
+    fn search_next(&mut self, forward: bool) {
+        if self.pattern.is_none() {
+            return;
+        }
+        match self.current_match {
+            Some((line, start)) if forward => self.find_from(line, start + 1, true),
+            Some((line, start)) => self.find_from(line, start, false),
+            None => self.find_from(self.top_line, 0, forward),
+        }
+    }
+
+    // (line, column)から後ろ（前）へ探すよう、検索スレッドを始める（前の検索はやめる）
+    fn find_from(&mut self, line: usize, column: usize, forward: bool) {
+        let Some(pattern) = self.pattern.clone() else {
+            return;
+        };
+        // 索引にまだない行からは読み始められないので、索引の最後の行から読む
+        let first_line = line.min(self.line_starts.len() - 1);
+        let job = SearchJob {
+            path: self.path.clone(),
+            encoding: self.encoding,
+            pattern,
+            target: (line, column),
+            first_line,
+            offset: self.line_starts[first_line],
+            forward,
+        };
+        self.searched_bytes = 0;
+        self.searcher = Some(spawn_search(job));
+    }
+
+    // 検索スレッドから届いた進み具合と結果を取り込む
+    fn poll_search(&mut self) {
+        let Some(searcher) = &self.searcher else {
+            return;
+        };
+        let result = loop {
+            match searcher.try_recv() {
+                Ok(SearchEvent::Progress(bytes)) => self.searched_bytes = bytes,
+                Ok(SearchEvent::Done(result)) => break result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => break Ok(None),
+            }
+        };
+        self.searcher = None;
+        match result {
+            Ok(Some(((line, start), wrapped))) => {
+                self.current_match = Some((line, start));
+                if line < self.known_lines() {
+                    self.scroll_to_line(line);
+                } else {
+                    self.jump(Jump::Line(line));
+                }
+                if wrapped {
+                    self.status_message = Some("Search wrapped".to_string());
+                }
+            }
+            Ok(None) => self.status_message = Some(format!("Pattern not found: {}", self.search.query)),
+            Err(e) => self.status_message = Some(format!("Search failed: {}", e)),
+        }
+    }
+}
+
+fn decode_line(encoding: &'static Encoding, line: usize, bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    let bytes = match Encoding::for_bom(bytes) {
+        Some((_, bom_len)) if line == 0 => &bytes[bom_len..],
+        _ => bytes,
+    };
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+// 別スレッドでファイルを順に読んで検索し、進み具合と結果を送る。受け取る側がいなくなったら止める
+fn spawn_search(job: SearchJob) -> Receiver<SearchEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let result = run_search(&job, &tx);
+        let _ = tx.send(SearchEvent::Done(result));
+    });
+    rx
+}
+
+// 前へ探すときはtarget以降の最初の一致を、なければ先頭から探し直す。
+// 後ろへ探すときはファイルを先頭から読み、targetより前の最後の一致を、なければ末尾に最も近い一致を返す
+fn run_search(job: &SearchJob, tx: &Sender<SearchEvent>) -> io::Result<Option<SearchHit>> {
+    let mut scanned = 0;
+    let mut found = None;
+    if job.forward {
+        scan_lines(job, tx, &mut scanned, job.first_line, job.offset, |line, starts| {
+            found = starts.iter().map(|&start| (line, start)).find(|&m| m >= job.target);
+            found.is_some()
+        })?;
+        if let Some(m) = found {
+            return Ok(Some((m, false)));
+        }
+        scan_lines(job, tx, &mut scanned, 0, 0, |line, starts| {
+            found = starts.first().map(|&start| (line, start));
+            found.is_some() || line >= job.target.0
+        })?;
+        return Ok(found.map(|m| (m, true)));
+    }
+    let mut after = None;
+    scan_lines(job, tx, &mut scanned, 0, 0, |line, starts| {
+        for &start in starts {
+            if (line, start) < job.target {
+                found = Some((line, start));
+            } else {
+                after = Some((line, start));
+            }
+        }
+        // targetの行を過ぎて前の一致が見つかっていれば、残りは読まなくてよい
+        line >= job.target.0 && found.is_some()
+    })?;
+    Ok(found.map(|m| (m, false)).or(after.map(|m| (m, true))))
+}
+
+// first_line（開始位置offset）から1行ずつ読み、一致箇所の位置を`visit`に渡す。`visit`がtrueを返したら止める
+fn scan_lines(
+    job: &SearchJob,
+    tx: &Sender<SearchEvent>,
+    scanned: &mut u64,
+    first_line: usize,
+    offset: u64,
+    mut visit: impl FnMut(usize, &[usize]) -> bool,
+) -> io::Result<()> {
+    let mut file = File::open(&job.path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(file);
+    let mut bytes = Vec::new();
+    let base = *scanned;
+    for line in first_line.. {
+        if !read_capped_line(&mut reader, &mut bytes)? {
+            break;
+        }
+        let text = decode_line(job.encoding, line, &bytes);
+        let starts: Vec<usize> = job
+            .pattern
+            .find_iter(&text)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.start())
+            .collect();
+        if visit(line, &starts) {
+            break;
+        }
+        if (line - first_line).is_multiple_of(PROGRESS_LINES) {
+            *scanned = base + reader.stream_position()? - offset;
+            if tx.send(SearchEvent::Progress(*scanned)).is_err() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "検索を中止しました"));
+            }
+        }
+    }
+    *scanned = base + reader.stream_position()? - offset;
+    Ok(())
+}
+
+// 1行を先頭のMAX_LINE_BYTESまでだけ読み、残りは次の改行まで読み飛ばす
+// （改行のない巨大なファイルでも1行分をまるごとメモリに読み込まない）。ファイルの終わりならfalse
+fn read_capped_line(reader: &mut impl BufRead, bytes: &mut Vec<u8>) -> io::Result<bool> {
+    bytes.clear();
+    if reader.take(MAX_LINE_BYTES).read_until(b'\n', bytes)? == 0 {
+        return Ok(false);
+    }
+    if bytes.last() != Some(&b'\n') {
+        reader.skip_until(b'\n')?;
+    }
+    Ok(true)
+}
+
+// 改行の位置を数えて、各行の開始位置を少しずつ送る。受け取る側がいなくなったら止める
+fn spawn_indexer(file_path: &Path) -> io::Result<Receiver<io::Result<Vec<u64>>>> {
+    let mut file = File::open(file_path)?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = vec![0; INDEX_CHUNK];
+        let mut offset = 0u64;
+        loop {
+            let read = match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    break;
+                }
+            };
+            let starts = buffer[..read]
+                .iter()
+                .enumerate()
+                .filter(|&(_, &byte)| byte == b'\n')
+                .map(|(i, _)| offset + i as u64 + 1)
+                .collect();
+            offset += read as u64;
+            if tx.send(Ok(starts)).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(rx)
+}
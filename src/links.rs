@@ -0,0 +1,123 @@
+// --- リンク先URLの扱い ---
+//
+// Markdown中のリンク・画像のURLを解析し、ローカルのファイルに対応づける。
+// 一括変換やブラウザでのプレビューでリンク先を書き換えるときに使う。
+
+use std::path::{Component, Path, PathBuf};
+
+use pulldown_cmark::{CowStr, Event, Tag};
+
+/// リンクと画像のURLを書き換える。`rewrite`には(URL, 画像ならtrue)を渡す
+pub fn rewrite_link_urls<'e>(
+    event: Event<'e>,
+    mut rewrite: impl FnMut(CowStr<'e>, bool) -> CowStr<'e>,
+) -> Event<'e> {
+    match event {
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: rewrite(dest_url, false),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: rewrite(dest_url, true),
+            title,
+            id,
+        }),
+        event => event,
+    }
+}
+
+/// 相対パスのURLか。"https:"や"mailto:"などのスキーム付き、"/abs"、"#anchor"のみは対象外
+pub fn is_relative_url(url: &str) -> bool {
+    if url.starts_with('/') || url.starts_with('#') {
+        return false;
+    }
+    let scheme_end = url.find(':');
+    let path_start = url.find(['/', '?', '#']).unwrap_or(url.len());
+    !matches!(scheme_end, Some(i) if i < path_start)
+}
+
+/// "."と".."を取り除く（ファイルが存在しなくても使えるよう、canonicalizeは使わない）
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// "%20"などをデコードする。不正な並びはそのまま残す
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// URLをパス部分と、"#"・"?"以降に分ける
+pub fn split_url(url: &str) -> (&str, &str) {
+    url.split_at(url.find(['#', '?']).unwrap_or(url.len()))
+}
+
+/// 相対URLのパス部分を、リンク元のディレクトリを基準にしたファイルパスにする
+pub fn resolve_path(base_dir: &Path, path_part: &str) -> PathBuf {
+    normalize(&base_dir.join(percent_decode(path_part)))
+}
+
+/// 絶対パスを"file://"のURLにする
+pub fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    // Windowsのドライブ名（C:/...）はそのまま残し、前に"/"を付ける
+    match path.split_at_checked(2) {
+        Some((drive, rest)) if drive.ends_with(':') => format!("file:///{}{}", drive, encode_path(rest)),
+        _ => format!("file://{}", encode_path(&path)),
+    }
+}
+
+/// URLのパスとして使えない文字を"%XX"にする（"/"は区切りとして残す）
+pub fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
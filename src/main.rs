@@ -4,22 +4,73 @@ use std::{
     fs,
     io::{self, stdout},
     path::{Path, PathBuf},
+    process::ExitCode,
     time::Duration,
 };
 
 use arboard::Clipboard; // クリップボード用
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-// pulldown_cmarkからhtmlモジュールをインポート
-use pulldown_cmark::{html, Options, Parser as MarkdownParser};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
 };
-use opener;
+use unicode_width::UnicodeWidthStr;
+
+mod batch;
+mod browser;
+mod check;
+mod cli;
+mod convert;
+mod encoding;
+mod export;
+mod file_ops;
+mod finder;
+mod front_matter;
+mod grep;
+mod hex;
+mod hints;
+mod html_lexer;
+mod large_file;
+mod links;
+mod markdown;
+mod outline;
+mod search;
+mod serve;
+mod slug;
+mod stats;
+mod syntax;
+mod watch;
+mod wrap;
+
+use batch::run_batch;
+use browser::open_in_browser;
+use check::check_links;
+use clap::Parser;
+use cli::{Cli, Command, ConvertArgs, EXIT_NOT_FOUND};
+use encoding::{Decoded, decode, encoding_for_label, is_binary_file, read_file, read_file_as};
+use encoding_rs::Encoding;
+use convert::run_convert;
+use file_ops::{FileOp, Undo};
+use finder::{FileFinder, FinderAction};
+use front_matter::read_front_matter;
+use grep::{CONTEXT_LINES, GrepAction, GrepHit, GrepView, MAX_HITS};
+use html_lexer::highlight_html;
+use hex::{HexPrompt, HexView};
+use hints::{HintAction, LinkHints};
+use large_file::{LARGE_FILE_BYTES, LargeFileView};
+use links::{is_relative_url, percent_decode, resolve_path, split_url};
+use markdown::{LinkEntry, SourceLine, is_markdown, markdown_to_html, render_markdown};
+use outline::{OutlineEntry, OutlinePane, section_at};
+use search::{Search, restyle_range};
+use serve::run_serve;
+use stats::{TextStats, WritingStats, markdown_stats, plain_stats};
+use syntax::highlight_source;
+use watch::FileWatcher;
+use wrap::{WrappedText, wrap_text};
 
 // --- 配色テーマ定義 ---
 struct ColorScheme {
@@ -29,6 +80,17 @@ struct ColorScheme {
     selection_fg: Color,
     comment: Color,
     link: Color,
+    border: Color,
+    code_fg: Color,
+    code_bg: Color,
+    headings: [Color; 6], // h1〜h6の文字色
+    html_tag: Color,
+    html_attr: Color,
+    html_value: Color,
+    html_entity: Color,
+    html_doctype: Color,
+    match_bg: Color,         // 検索の一致箇所
+    current_match_bg: Color, // 検索で選択中の一致箇所
 }
 
 const GITHUB_DARK_THEME: ColorScheme = ColorScheme {
@@ -38,13 +100,49 @@ const GITHUB_DARK_THEME: ColorScheme = ColorScheme {
     selection_fg: Color::Rgb(201, 209, 217),
     comment: Color::Rgb(139, 148, 158),  // #8b949e
     link: Color::Rgb(88, 166, 255),      // #58a6ff
+    border: Color::Rgb(48, 54, 61),      // #30363d
+    code_fg: Color::Rgb(255, 166, 87),   // #ffa657
+    code_bg: Color::Rgb(22, 27, 34),     // #161b22
+    headings: [
+        Color::Rgb(88, 166, 255),  // #58a6ff
+        Color::Rgb(121, 192, 255), // #79c0ff
+        Color::Rgb(126, 231, 135), // #7ee787
+        Color::Rgb(210, 168, 255), // #d2a8ff
+        Color::Rgb(255, 166, 87),  // #ffa657
+        Color::Rgb(139, 148, 158), // #8b949e
+    ],
+    html_tag: Color::Rgb(126, 231, 135),     // #7ee787
+    html_attr: Color::Rgb(121, 192, 255),    // #79c0ff
+    html_value: Color::Rgb(165, 214, 255),   // #a5d6ff
+    html_entity: Color::Rgb(255, 166, 87),   // #ffa657
+    html_doctype: Color::Rgb(255, 123, 114), // #ff7b72
+    match_bg: Color::Rgb(99, 70, 8),         // #634608
+    current_match_bg: Color::Rgb(210, 153, 34), // #d29922
 };
 
 // --- アプリケーションの状態管理 ---
 
+/// ディレクトリの中身を、ディレクトリを先に、それぞれ名前順に並べて返す
+fn read_sorted_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+
+    entries.sort_by(|a, b| {
+        let a_is_dir = a.is_dir();
+        let b_is_dir = b.is_dir();
+        a_is_dir.cmp(&b_is_dir).reverse().then_with(|| a.cmp(b))
+    });
+    Ok(entries)
+}
+
 enum AppMode {
     Explorer,
     Preview,
+    Hex,       // バイナリファイルの16進ダンプ
+    LargeFile, // 全体を読み込まずに表示する大きなファイル
+    Grep,      // ":grep"の検索結果
 }
 
 struct ExplorerState {
@@ -55,37 +153,60 @@ struct ExplorerState {
     is_error: bool,                 // メッセージがエラーかどうか
     command_input: String,
     in_command_mode: bool,
+    split_view: bool,                    // 右側にプレビューを並べて表示する
+    preview_focused: bool,               // 右側のプレビューにフォーカスがあるか
+    side_preview: Option<PreviewState>,  // 右側に表示中のプレビュー
+    side_preview_path: Option<PathBuf>,  // 右側のプレビューの対象（読み込み失敗時も記録する）
+    hidden_by: Vec<(String, String)>,    // フロントマターがこの(キー, 値)に一致するファイルは表示しない
+    finder: Option<FileFinder>,          // Ctrl-pで開いたファイル検索
+    finder_preview: Option<PreviewState>, // ファイル検索で選択中のファイルのプレビュー
+    finder_preview_path: Option<PathBuf>,
+    open_request: Option<PathBuf>, // キー操作の後に開くファイル
+    confirm: Option<FileOp>,       // y/nの確認を待っているファイル操作
+    last_undo: Option<Undo>,       // 直前のファイル操作の取り消し方
 }
 
 impl ExplorerState {
-    fn new() -> io::Result<Self> {
+    fn new(start_dir: PathBuf) -> io::Result<Self> {
         let mut state = Self {
-            current_path: env::current_dir()?,
+            current_path: start_dir,
             entries: Vec::new(),
             list_state: ListState::default(),
             status_message: None,
             is_error: false,
             command_input: String::new(),
             in_command_mode: false,
+            split_view: false,
+            preview_focused: false,
+            side_preview: None,
+            side_preview_path: None,
+            hidden_by: Vec::new(),
+            finder: None,
+            finder_preview: None,
+            finder_preview_path: None,
+            open_request: None,
+            confirm: None,
+            last_undo: None,
         };
         state.load_entries()?;
         Ok(state)
     }
 
-    /// ディレクトリ読み込み時にカーソル位置を必ずリセットする
+    /// 一覧を読み込み、カーソルを先頭に戻す（ディレクトリの移動と`:hide`・`:unhide`で使う）。
+    /// ファイル操作の後に読み直すときは、カーソル位置を保つ`reload_entries`を使う
     fn load_entries(&mut self) -> io::Result<()> {
-        let mut entries = fs::read_dir(&self.current_path)?
-            .filter_map(Result::ok)
-            .map(|entry| entry.path())
-            .collect::<Vec<_>>();
-
-        entries.sort_by(|a, b| {
-            let a_is_dir = a.is_dir();
-            let b_is_dir = b.is_dir();
-            a_is_dir.cmp(&b_is_dir).reverse().then_with(|| a.cmp(b))
-        });
-
-        self.entries = entries;
+        self.entries = read_sorted_entries(&self.current_path)?;
+        if !self.hidden_by.is_empty() {
+            let hidden_by = &self.hidden_by;
+            self.entries.retain(|path| {
+                !is_markdown(path)
+                    || read_front_matter(path).is_none_or(|front_matter| {
+                        !hidden_by
+                            .iter()
+                            .any(|(key, value)| front_matter.matches(key, value))
+                    })
+            });
+        }
 
         if !self.entries.is_empty() {
             self.list_state.select(Some(0));
@@ -95,6 +216,30 @@ impl ExplorerState {
         Ok(())
     }
 
+    /// 一覧を読み直す。`select`が一覧にあればそこに、なければ元の位置にカーソルを合わせる
+    fn reload_entries(&mut self, select: Option<&Path>) -> io::Result<()> {
+        let previous = self.list_state.selected();
+        self.load_entries()?;
+        if let Some(i) = previous
+            && !self.entries.is_empty()
+        {
+            self.list_state.select(Some(i.min(self.entries.len() - 1)));
+        }
+        if let Some(path) = select {
+            self.select_path(path);
+        }
+        Ok(())
+    }
+
+    /// 絞り込みの表示（例: "draft=true, status=wip"）
+    fn filter_label(&self) -> String {
+        self.hidden_by
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     fn next(&mut self) {
         if self.entries.is_empty() {
             return;
@@ -123,6 +268,257 @@ impl ExplorerState {
         self.list_state.select(Some(i));
     }
 
+    fn selected_path(&self) -> Option<&PathBuf> {
+        self.list_state.selected().and_then(|i| self.entries.get(i))
+    }
+
+    /// 一覧にあればそのファイルにカーソルを合わせる
+    fn select_path(&mut self, path: &Path) {
+        if let Some(i) = self.entries.iter().position(|entry| entry == path) {
+            self.list_state.select(Some(i));
+        }
+    }
+
+    /// ファイルのあるディレクトリへ移動し、そのファイルにカーソルを合わせる
+    fn reveal(&mut self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            self.current_path = dunce::canonicalize(parent)?;
+            self.load_entries()?;
+            self.select_path(&self.current_path.join(path.file_name().unwrap_or_default()));
+        }
+        Ok(())
+    }
+
+    fn close_finder(&mut self) {
+        self.finder = None;
+        self.finder_preview = None;
+        self.finder_preview_path = None;
+    }
+
+    /// ファイル検索の結果を取り込み、選択中のファイルが変わっていればプレビューを作り直す
+    fn refresh_finder_preview(&mut self, theme: &ColorScheme) {
+        let Some(finder) = &mut self.finder else {
+            return;
+        };
+        finder.poll();
+        let selected = finder.selected_path();
+        if selected == self.finder_preview_path {
+            return;
+        }
+        self.finder_preview = selected
+            .as_deref()
+            .and_then(|path| load_preview(path, theme).ok());
+        self.finder_preview_path = selected;
+    }
+
+    /// HTMLファイルはそのまま、Markdownは変換してブラウザで開く
+    fn open_in_browser(&mut self, file_path: &Path) {
+        let name = file_path
+            .file_name()
+            .map_or_else(|| file_path.to_string_lossy(), |s| s.to_string_lossy())
+            .to_string();
+        if !file_path.is_file() {
+            self.set_message(format!("ファイルが見つかりません: {}", name), true);
+        } else if !is_markdown(file_path) && !is_html(file_path) {
+            self.set_message("HTMLファイルとMarkdownファイルのみ開けます。".to_string(), true);
+        } else if let Err(e) = open_in_browser(file_path) {
+            self.set_message(format!("ブラウザで開けませんでした: {}", e), true);
+        } else {
+            self.set_message(format!("ブラウザで開きました: {}", name), false);
+        }
+    }
+
+    // コマンドの引数のパスを、表示中のディレクトリからの相対パスとして解決する。
+    // ".."を含んでいても一覧のパスと比べられるよう、親ディレクトリを正規化する
+    fn resolve(&self, arg: &str) -> PathBuf {
+        let path = self.current_path.join(arg);
+        match (path.parent().and_then(|parent| dunce::canonicalize(parent).ok()), path.file_name()) {
+            (Some(parent), Some(name)) => parent.join(name),
+            // ".."で終わるパスは全体を正規化する
+            _ => dunce::canonicalize(&path).unwrap_or(path),
+        }
+    }
+
+    // 表示中のディレクトリそのものか、その親ディレクトリか（ゴミ箱への移動や移動の対象にできない）
+    fn is_current_or_ancestor(&self, path: &Path) -> bool {
+        self.current_path.starts_with(path)
+    }
+
+    /// ":mkdir"・":touch"・":rename"・":cp"・":mv"・":rm"を処理する。
+    /// `arg`はコマンドの後ろの文字列全体（名前に空白を含めてもよい）
+    fn file_command(&mut self, command: &str, arg: &str) {
+        let selected = self.selected_path().cloned();
+        let op = match (command, selected) {
+            ("mkdir", _) if !arg.is_empty() => FileOp::Mkdir(self.resolve(arg)),
+            ("touch", _) if !arg.is_empty() => FileOp::Touch(self.resolve(arg)),
+            ("rm", _) if !arg.is_empty() => {
+                self.confirm_trash(self.resolve(arg));
+                return;
+            }
+            ("rm", Some(from)) => {
+                self.confirm_trash(from);
+                return;
+            }
+            ("rename", Some(from)) if !arg.is_empty() => {
+                if arg.contains(std::path::is_separator) {
+                    self.set_message(
+                        "名前にパスの区切りは使えません（別の場所へは:mvで移動します）".to_string(),
+                        true,
+                    );
+                    return;
+                }
+                let to = from.with_file_name(arg);
+                FileOp::Rename(from, to)
+            }
+            ("cp" | "mv", Some(from)) if !arg.is_empty() => {
+                // 既にあるディレクトリを指定したら、その中に同じ名前で置く
+                let mut to = self.resolve(arg);
+                if to.is_dir()
+                    && let Some(name) = from.file_name()
+                {
+                    to = to.join(name);
+                }
+                if command == "cp" {
+                    FileOp::Copy(from, to)
+                } else {
+                    FileOp::Move(from, to)
+                }
+            }
+            ("rename" | "cp" | "mv" | "rm", None) => {
+                self.set_message("対象のファイルが選択されていません。".to_string(), true);
+                return;
+            }
+            _ => {
+                let usage = match command {
+                    "mkdir" => ":mkdir <ディレクトリ名>",
+                    "touch" => ":touch <ファイル名>",
+                    "rename" => ":rename <新しい名前>",
+                    "cp" => ":cp <コピー先>",
+                    _ => ":mv <移動先>",
+                };
+                self.set_message(format!("使い方: {}", usage), true);
+                return;
+            }
+        };
+        self.run_file_op(op);
+    }
+
+    // ゴミ箱への移動は、確認してから行う
+    fn confirm_trash(&mut self, path: PathBuf) {
+        let name = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |s| s.to_string_lossy())
+            .to_string();
+        if path.symlink_metadata().is_err() {
+            self.set_message(format!("ファイルが見つかりません: {}", name), true);
+            return;
+        }
+        if self.is_current_or_ancestor(&path) {
+            self.set_message("表示中のディレクトリとその親ディレクトリはゴミ箱に移動できません。".to_string(), true);
+            return;
+        }
+        let kind = if path.is_dir() { "ディレクトリ" } else { "ファイル" };
+        self.set_message(format!("{}「{}」をゴミ箱に移動しますか？ (y/n)", kind, name), false);
+        self.confirm = Some(FileOp::Trash(path));
+    }
+
+    /// ファイル操作を行い、一覧を読み直して操作したファイルにカーソルを合わせる
+    fn run_file_op(&mut self, op: FileOp) {
+        if let FileOp::Trash(from) | FileOp::Rename(from, _) | FileOp::Move(from, _) = &op
+            && self.is_current_or_ancestor(from)
+        {
+            self.set_message("表示中のディレクトリとその親ディレクトリは対象にできません。".to_string(), true);
+            return;
+        }
+        match op.apply() {
+            Ok(undo) => {
+                self.last_undo = Some(undo);
+                self.set_message(op.describe(), false);
+                self.reload_after_file_op(op.target());
+            }
+            Err(e) => self.set_message(format!("ファイル操作に失敗しました: {}", e), true),
+        }
+    }
+
+    /// 直前のファイル操作を取り消す（取り消しの取り消しはしない）
+    fn undo_file_op(&mut self) {
+        let Some(undo) = self.last_undo.take() else {
+            self.set_message("取り消せる操作がありません。".to_string(), true);
+            return;
+        };
+        match undo.apply() {
+            Ok(path) => {
+                let path = path.to_path_buf();
+                self.set_message(undo.describe(), false);
+                self.reload_after_file_op(&path);
+            }
+            Err(e) => {
+                self.set_message(format!("取り消せませんでした: {}", e), true);
+                self.last_undo = Some(undo);
+            }
+        }
+    }
+
+    // ファイル操作の後に一覧を読み直す。表示中のディレクトリがなくなっていれば、
+    // 残っている一番近い親ディレクトリへ移る。読めなければエラーを表示する（終了はしない）
+    fn reload_after_file_op(&mut self, select: &Path) {
+        if self.reload_entries(Some(select)).is_ok() {
+            return;
+        }
+        while !self.current_path.is_dir()
+            && let Some(parent) = self.current_path.parent()
+        {
+            self.current_path = parent.to_path_buf();
+        }
+        if let Err(e) = self.load_entries() {
+            self.entries.clear();
+            self.list_state.select(None);
+            self.set_message(format!("一覧を読み込めませんでした: {}", e), true);
+        }
+    }
+
+    // 選択中のファイル名を入れた状態でコマンドモードを始める（例: "rename note.md"）
+    fn start_command(&mut self, command: &str, with_selected: bool) {
+        let name = self
+            .selected_path()
+            .filter(|_| with_selected)
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.command_input = format!("{} {}", command, name);
+        self.in_command_mode = true;
+    }
+
+    fn toggle_split_view(&mut self) {
+        self.split_view = !self.split_view;
+        self.preview_focused = false;
+        if !self.split_view {
+            self.side_preview = None;
+            self.side_preview_path = None;
+        }
+    }
+
+    /// 分割表示中、カーソル位置のファイルが変わっていれば右側のプレビューを作り直す
+    fn refresh_side_preview(&mut self, theme: &ColorScheme) {
+        if !self.split_view {
+            return;
+        }
+        let selected = self.selected_path().cloned();
+        if selected == self.side_preview_path {
+            if let Some(state) = &mut self.side_preview {
+                state.reload_if_changed(theme);
+            }
+            return;
+        }
+
+        self.side_preview = selected
+            .as_deref()
+            .filter(|path| path.is_file())
+            .and_then(|path| load_preview(path, theme).ok());
+        self.side_preview_path = selected;
+        self.preview_focused &= self.side_preview.is_some();
+    }
+
     fn set_message(&mut self, message: String, is_error: bool) {
         self.status_message = Some(message);
         self.is_error = is_error;
@@ -134,67 +530,790 @@ impl ExplorerState {
     }
 }
 
+// 表示中のテキストでの位置（行, 行の中のバイト位置）
+type TextPosition = (usize, usize);
+
 struct PreviewState {
     content: Text<'static>,
-    original_text: String, // コピー用に原文を保持
-    scroll: u16,
+    rendered: Option<Text<'static>>, // Markdownの場合のみ、整形表示用のテキストを持つ
+    show_rendered: bool,             // 整形表示とHTMLソース表示の切り替え
+    original_text: String,           // コピー用に原文を保持
+    scroll: usize,                   // 先頭に表示している画面上の行（折り返し後の行単位）
+    wrapped: Option<WrappedText>,    // 表示幅で折り返したテキスト（描画時に作る）
+    viewport_height: usize,          // 表示領域の高さ（ページ単位の移動に使う）
+    pending_count: Option<usize>,    // "20j" などの回数指定
+    pending_key: Option<char>,       // "gg"・"]]"・"[["の1文字目を入力済み
+    line_prompt: Option<String>,     // ":<行番号>"・":enc <文字コード>" を入力中
+    file_path: PathBuf,
     title: String,
-    char_count: usize,
+    char_count: usize,              // 原文の文字数（タグなども含む）
+    stats: WritingStats,            // 本文だけを数えた執筆用の統計
+    show_stats: bool,               // フッターに文字数の代わりに統計を表示する
+    encoding: Option<String>,       // 読み込んだファイルの文字コード（"Shift_JIS"など）
+    encoding_override: Option<&'static Encoding>, // ":enc"で指定した文字コード
     status_message: Option<String>, // "Copied!" などの一時メッセージ
     clipboard: Option<Clipboard>,   // Clipboardインスタンスを保持して早期Dropを防ぐ
+    watcher: Option<FileWatcher>,   // ファイルが書き換えられたら再読み込みする
+    search: Search,
+    search_prompt: Option<SearchPrompt>, // 検索語を入力中かどうか
+    outline: Vec<OutlineEntry>,          // 整形表示中の見出し（Markdownのみ）
+    html_heading_lines: Vec<usize>,      // HTMLソース表示での見出しの行番号
+    outline_pane: Option<OutlinePane>,   // アウトラインペインを表示中
+    links: Vec<LinkEntry>,               // 整形表示中のリンク（Markdownのみ）
+    source_lines: Vec<SourceLine>,       // 元の行の整形表示での位置（Markdownのみ）
+    link_hints: Option<LinkHints>,       // リンクを選択中
+    jump_back: Vec<usize>,               // 文書内のリンクで移動する前の先頭の行
+    request: Option<PreviewRequest>,     // プレビューの外で処理する操作
+}
+
+// 別の文書への移動はプレビューを作り直すので、呼び出し側で処理する
+enum PreviewRequest {
+    Open(PathBuf, Option<String>), // ファイルを開く（"#見出し"があればその位置へ）
+    Back,                          // 前の文書に戻る
+}
+
+// 検索語の入力を取り消したときに戻す状態
+struct SearchPrompt {
+    saved_scroll: usize,
+    saved_query: String,
 }
 
 impl PreviewState {
-    // プレーンテキスト表示用
+    // プレーンテキスト表示用（ソースファイルは言語に応じてハイライト）
     fn new_text(file_path: &Path, content_str: String, theme: &ColorScheme) -> Self {
         let char_count = content_str.chars().count();
-        let content = Text::styled(content_str.clone(), Style::default().fg(theme.fg));
-
-        // Clipboardの初期化をここで行い、インスタンスを保持する
-        let clipboard = Clipboard::new().ok();
+        let stats = plain_stats(&content_str);
+        let content = highlight_source(file_path, &content_str, Style::default().fg(theme.fg));
 
         Self {
             content,
+            rendered: None,
+            show_rendered: false,
             original_text: content_str,
             scroll: 0,
+            wrapped: None,
+            viewport_height: 1,
+            pending_count: None,
+            pending_key: None,
+            line_prompt: None,
+            file_path: file_path.to_path_buf(),
             title: file_path.to_string_lossy().to_string(),
             char_count,
+            stats,
+            show_stats: false,
+            encoding: None,
+            encoding_override: None,
             status_message: None,
-            clipboard,
+            clipboard: None, // 最初にコピーするときに初期化する
+            watcher: FileWatcher::new(file_path).ok(),
+            search: Search::default(),
+            search_prompt: None,
+            outline: Vec::new(),
+            html_heading_lines: Vec::new(),
+            outline_pane: None,
+            links: Vec::new(),
+            source_lines: Vec::new(),
+            link_hints: None,
+            jump_back: Vec::new(),
+            request: None,
         }
     }
 
-    // HTMLソース表示用（簡易ハイライト付き）
+    // HTMLソース表示用（タグ・属性・文字参照などを色分け）
     fn new_html(file_path: &Path, html_source: String, theme: &ColorScheme) -> Self {
         let char_count = html_source.chars().count();
         // ハイライト処理
         let content = highlight_html(&html_source, theme);
 
-        // Clipboardの初期化をここで行い、インスタンスを保持する
-        let clipboard = Clipboard::new().ok();
-
         Self {
             content,
+            rendered: None,
+            show_rendered: false,
             original_text: html_source,
             scroll: 0,
+            wrapped: None,
+            viewport_height: 1,
+            pending_count: None,
+            pending_key: None,
+            line_prompt: None,
+            file_path: file_path.to_path_buf(),
             title: file_path.to_string_lossy().to_string(),
             char_count,
+            stats: WritingStats::default(),
+            show_stats: false,
+            encoding: None,
+            encoding_override: None,
             status_message: None,
-            clipboard,
+            clipboard: None, // 最初にコピーするときに初期化する
+            watcher: FileWatcher::new(file_path).ok(),
+            search: Search::default(),
+            search_prompt: None,
+            outline: Vec::new(),
+            html_heading_lines: Vec::new(),
+            outline_pane: None,
+            links: Vec::new(),
+            source_lines: Vec::new(),
+            link_hints: None,
+            jump_back: Vec::new(),
+            request: None,
         }
     }
 
-    fn scroll_up(&mut self) {
-        self.scroll = self.scroll.saturating_sub(1);
+    // Markdown表示用（整形表示とHTMLソースを切り替え可能）
+    fn new_markdown(file_path: &Path, markdown_input: &str, theme: &ColorScheme) -> Self {
+        let mut state = Self::new_html(file_path, String::new(), theme);
+        state.set_markdown(markdown_input, theme);
+        state.show_rendered = true;
+        state
+    }
+
+    // Markdownから整形表示・HTMLソース・見出しの一覧を作り直す
+    fn set_markdown(&mut self, markdown_input: &str, theme: &ColorScheme) {
+        let html_source = markdown_to_html(markdown_input);
+        let rendered = render_markdown(markdown_input, theme);
+        self.rendered = Some(rendered.text);
+        self.outline = rendered.outline;
+        self.stats = markdown_stats(markdown_input);
+        self.links = rendered.links;
+        self.source_lines = rendered.source_lines;
+        // HTMLでは見出しが1行に1つずつ"<h1>"のように始まる
+        self.html_heading_lines = html_source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| {
+                let bytes = line.as_bytes();
+                bytes.len() >= 3 && bytes[0] == b'<' && bytes[1] == b'h' && (b'1'..=b'6').contains(&bytes[2])
+            })
+            .map(|(i, _)| i)
+            .collect();
+        self.content = highlight_html(&html_source, theme);
+        self.char_count = html_source.chars().count();
+        self.original_text = html_source;
+        if let Some(pane) = &mut self.outline_pane {
+            pane.selected = pane.selected.min(self.outline.len().saturating_sub(1));
+            pane.collapsed.retain(|&i| i < self.outline.len());
+        }
     }
 
-    fn scroll_down(&mut self) {
-        let max_scroll = self.content.height().saturating_sub(1) as u16;
-        if self.scroll < max_scroll {
-            self.scroll = self.scroll.saturating_add(1);
+    // 現在の表示モードのテキスト（検索の強調表示を含まない）
+    fn base_text(&self) -> &Text<'static> {
+        match &self.rendered {
+            Some(rendered) if self.show_rendered => rendered,
+            _ => &self.content,
         }
     }
 
+    // 描画するテキスト。検索中は一致箇所を強調表示したものになる
+    fn current_text(&self) -> &Text<'static> {
+        self.search
+            .highlighted()
+            .unwrap_or_else(|| self.base_text())
+    }
+
+    // フッターなどに表示する現在の表示モード
+    fn view_label(&self) -> &'static str {
+        match (&self.rendered, self.show_rendered) {
+            (Some(_), true) => "[Rendered] ",
+            (Some(_), false) => "[HTML] ",
+            (None, _) => "",
+        }
+    }
+
+    fn toggle_view(&mut self, theme: &ColorScheme) {
+        if self.rendered.is_some() {
+            self.show_rendered = !self.show_rendered;
+            // 表示モードごとに行数が異なるので先頭に戻す
+            self.scroll = 0;
+            self.refresh_search(theme);
+        }
+    }
+
+    /// 描画前に表示領域の大きさを設定し、幅が変わっていれば折り返し直す
+    fn set_viewport(&mut self, width: u16, height: u16) {
+        self.viewport_height = height.max(1) as usize;
+        if self.wrapped.as_ref().is_none_or(|wrapped| wrapped.width != width) {
+            self.layout(width);
+        }
+        self.clamp_scroll();
+    }
+
+    // 描画するテキストが変わったら折り返しを作り直す
+    fn relayout(&mut self) {
+        if let Some(width) = self.wrapped.as_ref().map(|wrapped| wrapped.width) {
+            self.layout(width);
+        }
+    }
+
+    // テキストを折り返す。先頭に表示していた論理行が引き続き先頭に来るようにする
+    fn layout(&mut self, width: u16) {
+        let (top_line, offset) = match &self.wrapped {
+            Some(wrapped) => {
+                let line = wrapped.row_to_line(self.scroll);
+                (line, self.scroll - wrapped.line_to_row(line))
+            }
+            None => (self.scroll, 0),
+        };
+        let wrapped = wrap_text(self.current_text(), width);
+        let line_rows = wrapped.line_to_row(top_line + 1) - wrapped.line_to_row(top_line);
+        self.scroll = wrapped.line_to_row(top_line) + offset.min(line_rows.saturating_sub(1));
+        self.wrapped = Some(wrapped);
+        self.clamp_scroll();
+    }
+
+    // 折り返し後の総行数（まだ描画していなければ論理行数）
+    fn total_rows(&self) -> usize {
+        self.wrapped
+            .as_ref()
+            .map_or_else(|| self.current_text().height(), |wrapped| wrapped.rows.len())
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.total_rows().saturating_sub(self.viewport_height)
+    }
+
+    fn clamp_scroll(&mut self) {
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+
+    // 先頭に表示している論理行（0始まり）
+    fn top_line(&self) -> usize {
+        self.wrapped
+            .as_ref()
+            .map_or(self.scroll, |wrapped| wrapped.row_to_line(self.scroll))
+    }
+
+    /// フッターに表示する位置（例: "Ln 120/2000 45%"）
+    fn position_label(&self) -> String {
+        let total_lines = self.current_text().height();
+        let total_rows = self.total_rows();
+        let percent = if total_rows <= self.viewport_height {
+            100
+        } else {
+            (self.scroll + self.viewport_height) * 100 / total_rows
+        };
+        format!(
+            "Ln {}/{} {}%",
+            (self.top_line() + 1).min(total_lines),
+            total_lines,
+            percent
+        )
+    }
+
+    // 監視中のファイルが書き換えられていれば読み込み直す
+    fn reload_if_changed(&mut self, theme: &ColorScheme) {
+        if !self.watcher.as_ref().is_some_and(FileWatcher::changed) {
+            return;
+        }
+
+        match read_file_as(&self.file_path, self.encoding_override) {
+            Ok(decoded) => {
+                self.set_decoded(decoded, theme);
+                self.status_message = Some("Reloaded".to_string());
+            }
+            Err(e) => self.status_message = Some(format!("Reload failed: {}", e)),
+        }
+    }
+
+    // 読み直した内容に差し替える。スクロール位置はできるだけ維持し、短くなった場合は末尾に合わせる
+    fn set_decoded(&mut self, decoded: Decoded, theme: &ColorScheme) {
+        self.encoding = Some(decoded.label());
+        let text = decoded.text;
+        if self.rendered.is_some() {
+            self.set_markdown(&text, theme);
+        } else {
+            self.content = highlight_source(&self.file_path, &text, Style::default().fg(theme.fg));
+            self.stats = plain_stats(&text);
+            self.original_text = text;
+            self.char_count = self.original_text.chars().count();
+        }
+        self.refresh_search(theme);
+    }
+
+    // 文字コードを指定して読み直す。"auto"なら自動判定に戻す
+    fn reopen_with_encoding(&mut self, label: &str, theme: &ColorScheme) {
+        let encoding = match label {
+            "auto" => None,
+            label => match encoding_for_label(label) {
+                Some(encoding) => Some(encoding),
+                None => {
+                    self.status_message = Some(format!("Unknown encoding: {}", label));
+                    return;
+                }
+            },
+        };
+        match read_file_as(&self.file_path, encoding) {
+            Ok(decoded) => {
+                self.encoding_override = encoding;
+                self.set_decoded(decoded, theme);
+                self.status_message = Some(format!(
+                    "Reopened as {}",
+                    self.encoding.as_deref().unwrap_or_default()
+                ));
+            }
+            Err(e) => self.status_message = Some(format!("Reopen failed: {}", e)),
+        }
+    }
+
+    fn scroll_to_line(&mut self, line: usize) {
+        self.scroll = match &self.wrapped {
+            Some(wrapped) => wrapped.line_to_row(line),
+            None => line,
+        };
+        self.clamp_scroll();
+    }
+
+    // 表示中のテキストが変わったので、検索結果を作り直す（スクロール位置は動かさない）
+    fn refresh_search(&mut self, theme: &ColorScheme) {
+        let mut search = std::mem::take(&mut self.search);
+        search.update(self.base_text(), self.top_line(), theme);
+        self.search = search;
+        self.relayout();
+    }
+
+    fn clear_search(&mut self) {
+        self.search.clear();
+        self.relayout();
+    }
+
+    // 検索語を変えるたびに検索し直し、入力開始時の位置以降で最初の一致箇所に移動する
+    fn update_search(&mut self, theme: &ColorScheme) {
+        let from_row = self
+            .search_prompt
+            .as_ref()
+            .map_or(self.scroll, |prompt| prompt.saved_scroll);
+        let from_line = self
+            .wrapped
+            .as_ref()
+            .map_or(from_row, |wrapped| wrapped.row_to_line(from_row));
+        let mut search = std::mem::take(&mut self.search);
+        search.update(self.base_text(), from_line, theme);
+        self.search = search;
+        self.relayout();
+        match self.search.current_line() {
+            Some(line) => self.scroll_to_line(line),
+            None => {
+                if let Some(prompt) = &self.search_prompt {
+                    self.scroll = prompt.saved_scroll;
+                }
+            }
+        }
+    }
+
+    fn start_search(&mut self) {
+        self.search_prompt = Some(SearchPrompt {
+            saved_scroll: self.scroll,
+            saved_query: std::mem::take(&mut self.search.query),
+        });
+        self.clear_search();
+    }
+
+    // 検索語の入力中のキー操作
+    fn handle_search_key(&mut self, code: KeyCode, modifiers: KeyModifiers, theme: &ColorScheme) {
+        match code {
+            // Alt+r・Alt+cで正規表現・大文字小文字の区別を切り替える
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::ALT) => {
+                self.search.regex = !self.search.regex;
+                self.update_search(theme);
+            }
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::ALT) => {
+                self.search.ignore_case = !self.search.ignore_case;
+                self.update_search(theme);
+            }
+            KeyCode::Char(c) => {
+                self.search.query.push(c);
+                self.update_search(theme);
+            }
+            KeyCode::Backspace => {
+                self.search.query.pop();
+                self.update_search(theme);
+            }
+            KeyCode::Enter => self.search_prompt = None,
+            KeyCode::Esc => {
+                // 入力前の検索語とスクロール位置に戻す
+                if let Some(prompt) = self.search_prompt.take() {
+                    self.search.query = prompt.saved_query;
+                    self.scroll = prompt.saved_scroll;
+                    self.refresh_search(theme);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn search_next(&mut self, forward: bool, theme: &ColorScheme) {
+        let mut search = std::mem::take(&mut self.search);
+        let line = search.step(forward, self.base_text(), theme);
+        self.search = search;
+        self.relayout();
+        match line {
+            Some(line) => self.scroll_to_line(line),
+            None if !self.search.query.is_empty() => {
+                self.status_message = Some(format!("Pattern not found: {}", self.search.query));
+            }
+            None => {}
+        }
+    }
+
+    // ":grep"の一致箇所を表示する。同じパターンで検索し、ファイルの中でnth番目の一致箇所へ移動する
+    // （整形表示では原文と行の位置が変わるので、行番号ではなく何番目の一致かで探す）
+    fn show_grep_hit(&mut self, grep: &GrepView, hit: &GrepHit, theme: &ColorScheme) {
+        self.search.query = grep.pattern.clone();
+        self.search.regex = true;
+        self.search.ignore_case = grep.ignore_case;
+        let mut search = std::mem::take(&mut self.search);
+        search.update(self.base_text(), 0, theme);
+        // 整形表示では一致箇所の数が元のファイルと異なるので、一致した行が描画された位置から探す
+        let (index, line) = if self.show_rendered && self.rendered.is_some() {
+            let (start, end) = self.rendered_range(hit.line);
+            let index = start.and_then(|start| {
+                search.matches.iter().position(|m| {
+                    (m.line, m.start) >= start && end.is_none_or(|end| (m.line, m.start) < end)
+                })
+            });
+            // 描画されていなければ、その次に描画された行（なければ末尾）を表示する
+            let last_line = self.base_text().lines.len().saturating_sub(1);
+            (index, start.or(end).map_or(last_line, |(line, _)| line))
+        } else {
+            (Some(hit.nth), 0)
+        };
+        let line = match index {
+            Some(index) => search.select(index, self.base_text(), theme),
+            None => {
+                // リンク先のURLなど、整形表示では表示されない部分に一致した
+                self.status_message = Some(format!("Match on line {} is not shown in the rendered view", hit.line + 1));
+                Some(line)
+            }
+        };
+        self.search = search;
+        self.relayout();
+        if let Some(line) = line {
+            self.scroll_to_line(line);
+        }
+    }
+
+    // 元のファイルの行が整形表示で描画された範囲。終わりは次に描画された行の位置。
+    // 空行やリンクの定義など、その行が描画されていなければ始まりはNone
+    fn rendered_range(&self, source: usize) -> (Option<TextPosition>, Option<TextPosition>) {
+        let position = |entry: &SourceLine| (entry.line, entry.column);
+        let start = self
+            .source_lines
+            .iter()
+            .filter(|entry| entry.source == source)
+            .map(position)
+            .min();
+        let end = self
+            .source_lines
+            .iter()
+            .filter(|entry| entry.source > source)
+            .map(position)
+            .filter(|&end| start.is_none_or(|start| end > start))
+            .min();
+        (start, end)
+    }
+
+    fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    fn scroll_down(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_add(rows).min(self.max_scroll());
+    }
+
+    // 行番号の入力中のキー操作
+    fn handle_line_prompt_key(&mut self, code: KeyCode, theme: &ColorScheme) {
+        let Some(input) = &mut self.line_prompt else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let input = self.line_prompt.take().unwrap_or_default();
+                // ":enc <文字コード>"は文字コードを指定して読み直す
+                if let Some(label) = input.trim().strip_prefix("enc ") {
+                    self.reopen_with_encoding(label.trim(), theme);
+                    return;
+                }
+                match input.trim().parse::<usize>() {
+                    Ok(line) => self.scroll_to_line(line.saturating_sub(1)),
+                    Err(_) if input.trim().is_empty() => {}
+                    Err(_) => {
+                        self.status_message = Some(format!("Invalid line number: {}", input));
+                    }
+                }
+            }
+            KeyCode::Esc => self.line_prompt = None,
+            _ => {}
+        }
+    }
+
+    /// プレビュー画面のキー操作（閉じる操作は呼び出し側で扱う）
+    fn handle_key(&mut self, key: KeyEvent, theme: &ColorScheme) {
+        self.status_message = None; // 操作時に一時メッセージを消す
+        if self.search_prompt.is_some() {
+            self.handle_search_key(key.code, key.modifiers, theme);
+            return;
+        }
+        if self.line_prompt.is_some() {
+            self.handle_line_prompt_key(key.code, theme);
+            return;
+        }
+        if self.outline_pane.as_ref().is_some_and(|pane| pane.focused) {
+            self.handle_outline_key(key.code);
+            return;
+        }
+        if let Some(hints) = &mut self.link_hints {
+            match hints.handle_key(key.code) {
+                HintAction::None => {}
+                HintAction::Cancel => self.link_hints = None,
+                HintAction::Follow(index) => {
+                    self.link_hints = None;
+                    self.follow_link(index);
+                }
+            }
+            return;
+        }
+
+        // 数字は次の移動の回数として溜めておく（先頭の0は回数にしない）
+        if let KeyCode::Char(c @ '0'..='9') = key.code
+            && (c != '0' || self.pending_count.is_some())
+        {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            let count = self.pending_count.unwrap_or(0);
+            self.pending_count = Some(count.saturating_mul(10).saturating_add(digit));
+            return;
+        }
+        let count = self.pending_count.take();
+        let times = count.unwrap_or(1);
+        let pending_key = self.pending_key.take();
+        let half_page = (self.viewport_height / 2).max(1);
+        let page = self.viewport_height.saturating_sub(2).max(1); // 前後の2行は重ねて表示する
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Char('d') if ctrl => self.scroll_down(half_page * times),
+            KeyCode::Char('u') if ctrl => self.scroll_up(half_page * times),
+            KeyCode::Char('f') if ctrl => self.scroll_down(page * times),
+            KeyCode::Char('b') if ctrl => self.scroll_up(page * times),
+            KeyCode::PageDown => self.scroll_down(page * times),
+            KeyCode::PageUp => self.scroll_up(page * times),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_up(times),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_down(times),
+            // "gg"・"G"は先頭・末尾へ、回数指定があればその行へ移動する
+            KeyCode::Char('g') if pending_key == Some('g') => self.scroll_to_line(times - 1),
+            // "]]"・"[["は次・前の見出しへ移動する
+            KeyCode::Char(']') if pending_key == Some(']') => self.jump_heading(true, times),
+            KeyCode::Char('[') if pending_key == Some('[') => self.jump_heading(false, times),
+            KeyCode::Char(c @ ('g' | ']' | '[')) => {
+                self.pending_key = Some(c);
+                self.pending_count = count;
+            }
+            KeyCode::Char('t') => self.toggle_outline(),
+            KeyCode::Char('c') => self.show_stats = !self.show_stats, // 統計と文字数の切り替え
+            KeyCode::Char('f') => self.start_link_hints(),
+            KeyCode::Char('o') if ctrl => self.go_back(),
+            KeyCode::Backspace => self.go_back(),
+            KeyCode::Tab if self.outline_pane.is_some() => {
+                if let Some(pane) = &mut self.outline_pane {
+                    pane.focused = true;
+                }
+            }
+            KeyCode::Char('G') => match count {
+                Some(line) => self.scroll_to_line(line.saturating_sub(1)),
+                None => self.scroll = self.max_scroll(),
+            },
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::End => self.scroll = self.max_scroll(),
+            KeyCode::Char(':') => self.line_prompt = Some(String::new()),
+            KeyCode::Char('y') => self.copy_to_clipboard(), // 'y'でコピー
+            KeyCode::Char('o') => self.open_in_browser(),
+            KeyCode::Char('v') => self.toggle_view(theme), // 整形表示とHTMLソースの切り替え
+            KeyCode::Char('/') => self.start_search(),
+            KeyCode::Char('n') => self.search_next(true, theme),
+            KeyCode::Char('N') => self.search_next(false, theme),
+            KeyCode::Esc => self.clear_search(), // 検索の強調表示を消す
+            _ => {}
+        }
+    }
+
+    // Markdown・HTMLファイルをブラウザで開く
+    fn open_in_browser(&mut self) {
+        let message = if !is_markdown(&self.file_path) && !is_html(&self.file_path) {
+            "Only Markdown and HTML files can be opened in the browser".to_string()
+        } else if let Err(e) = open_in_browser(&self.file_path) {
+            format!("Failed to open in browser: {}", e)
+        } else {
+            "Opened in browser".to_string()
+        };
+        self.status_message = Some(message);
+    }
+
+    /// 検索語や行番号の入力中、またはアウトラインやリンクを操作中か（'q'などをここで扱う）
+    fn is_prompting(&self) -> bool {
+        self.search_prompt.is_some()
+            || self.line_prompt.is_some()
+            || self.link_hints.is_some()
+            || self.outline_pane.as_ref().is_some_and(|pane| pane.focused)
+    }
+
+    // リンクの一覧を開く。最初は画面に表示しているリンクを選択しておく
+    fn start_link_hints(&mut self) {
+        if self.links.is_empty() {
+            self.status_message = Some("No links".to_string());
+            return;
+        }
+        let top = self.top_line();
+        let selected = if self.show_rendered {
+            self.links.iter().position(|link| link.line >= top).unwrap_or(0)
+        } else {
+            0
+        };
+        self.link_hints = Some(LinkHints::new(self.links.len(), selected));
+    }
+
+    // リンク先へ移動する。外部のURLはブラウザなどに任せる
+    fn follow_link(&mut self, index: usize) {
+        let Some(url) = self.links.get(index).map(|link| link.url.clone()) else {
+            return;
+        };
+        if let Some(fragment) = url.strip_prefix('#') {
+            let top = self.top_line();
+            if self.jump_to_anchor(&percent_decode(fragment)) {
+                self.jump_back.push(top);
+            }
+            return;
+        }
+        if !is_relative_url(&url) {
+            self.status_message = Some(match opener::open(&url) {
+                Ok(()) => format!("Opened {}", url),
+                Err(e) => format!("Failed to open {}: {}", url, e),
+            });
+            return;
+        }
+
+        let (path_part, suffix) = split_url(&url);
+        let fragment = suffix
+            .split('?')
+            .next()
+            .and_then(|s| s.strip_prefix('#'))
+            .map(percent_decode);
+        let base_dir = self.file_path.parent().unwrap_or(Path::new("/"));
+        let target = resolve_path(base_dir, path_part);
+        if target.is_file() {
+            self.request = Some(PreviewRequest::Open(target, fragment));
+        } else {
+            self.status_message = Some(format!("File not found: {}", path_part));
+        }
+    }
+
+    /// "#見出し"の見出しへ移動する。見つからなければfalse
+    fn jump_to_anchor(&mut self, id: &str) -> bool {
+        let line = self
+            .outline
+            .iter()
+            .position(|entry| entry.id == id)
+            .and_then(|index| self.heading_lines().get(index).copied());
+        match line {
+            Some(line) => {
+                self.scroll_to_line(line);
+                true
+            }
+            None => {
+                self.status_message = Some(format!("Heading not found: #{}", id));
+                false
+            }
+        }
+    }
+
+    // 文書内で移動した位置を戻り、なければ前の文書に戻る
+    fn go_back(&mut self) {
+        match self.jump_back.pop() {
+            Some(line) => self.scroll_to_line(line),
+            None => self.request = Some(PreviewRequest::Back),
+        }
+    }
+
+    // 表示モードごとの見出しの行番号（整形表示とHTMLソースで異なる）
+    fn heading_lines(&self) -> Vec<usize> {
+        match &self.rendered {
+            Some(_) if self.show_rendered => self.outline.iter().map(|entry| entry.line).collect(),
+            Some(_) => self.html_heading_lines.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    // 先頭に表示している行より後（前）の見出しへ移動する
+    fn jump_heading(&mut self, forward: bool, times: usize) {
+        let lines = self.heading_lines();
+        let top = self.top_line();
+        let target = if forward {
+            lines.iter().filter(|&&line| line > top).nth(times - 1)
+        } else {
+            lines.iter().rev().filter(|&&line| line < top).nth(times - 1)
+        };
+        match target {
+            Some(&line) => self.scroll_to_line(line),
+            None => self.status_message = Some("No more headings".to_string()),
+        }
+    }
+
+    // アウトラインペインの表示を切り替える。開いたときは現在の節の見出しを選択する
+    fn toggle_outline(&mut self) {
+        if self.outline_pane.take().is_some() {
+            return;
+        }
+        if self.outline.is_empty() {
+            self.status_message = Some("No headings".to_string());
+            return;
+        }
+        self.outline_pane = Some(OutlinePane {
+            selected: section_at(&self.heading_lines(), self.top_line()).unwrap_or(0),
+            collapsed: Vec::new(),
+            focused: true,
+        });
+    }
+
+    // アウトラインペインにフォーカスがあるときのキー操作
+    fn handle_outline_key(&mut self, code: KeyCode) {
+        let Some(pane) = &mut self.outline_pane else {
+            return;
+        };
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => pane.move_selection(&self.outline, true, 1),
+            KeyCode::Up | KeyCode::Char('k') => pane.move_selection(&self.outline, false, 1),
+            KeyCode::Left | KeyCode::Char('h') => pane.set_collapsed(&self.outline, true),
+            KeyCode::Right | KeyCode::Char('l') => pane.set_collapsed(&self.outline, false),
+            KeyCode::Tab => pane.focused = false, // ペインを開いたまま本文を操作する
+            KeyCode::Enter => {
+                let selected = pane.selected;
+                if let Some(&line) = self.heading_lines().get(selected) {
+                    self.scroll_to_line(line);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('t') | KeyCode::Char('q') => self.outline_pane = None,
+            _ => {}
+        }
+    }
+
+    /// 表示領域に収まる分だけの行（全体を毎回複製しないようにする）
+    fn visible_text(&self) -> Text<'static> {
+        let Some(wrapped) = &self.wrapped else {
+            return Text::default();
+        };
+        let end = (self.scroll + self.viewport_height).min(wrapped.rows.len());
+        let start = self.scroll.min(end);
+        Text::from(wrapped.rows[start..end].to_vec())
+    }
+
+    /// 入力途中の回数指定などの表示（例: "20"、"g"）
+    fn pending_keys(&self) -> String {
+        let mut keys = self.pending_count.map(|n| n.to_string()).unwrap_or_default();
+        keys.extend(self.pending_key);
+        keys
+    }
+
     fn copy_to_clipboard(&mut self) {
         // 保持しているインスタンスを使用する
         // インスタンスがない場合（初期化失敗時など）は再作成を試みる
@@ -215,120 +1334,216 @@ impl PreviewState {
     }
 }
 
-// 簡易HTMLハイライト関数
-fn highlight_html(html_source: &str, theme: &ColorScheme) -> Text<'static> {
-    let mut lines = Vec::new();
+// ブラウザでそのまま開けるHTMLファイルか
+fn is_html(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html"))
+}
+
+/// ファイルを開いてプレビューを作る。.mdファイルは整形表示、それ以外はテキスト表示にする。
+/// 文字コードは自動で判定する
+fn load_preview(file_path: &Path, theme: &ColorScheme) -> io::Result<PreviewState> {
+    if is_binary_file(file_path)? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "バイナリファイルのため表示できません",
+        ));
+    }
+    if is_large_file(file_path) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "ファイルが大きすぎるため、ここでは表示できません",
+        ));
+    }
+    let decoded = decode(&fs::read(file_path)?);
+    let mut state = if is_markdown(file_path) {
+        PreviewState::new_markdown(file_path, &decoded.text, theme)
+    } else {
+        PreviewState::new_text(file_path, decoded.text.clone(), theme)
+    };
+    state.encoding = Some(decoded.label());
+    Ok(state)
+}
 
-    for line in html_source.lines() {
-        let mut spans = Vec::new();
-        let mut current_text = String::new();
-        let mut in_tag = false;
+/// 全体を読み込まずに表示する大きさのファイルか
+fn is_large_file(file_path: &Path) -> bool {
+    fs::metadata(file_path).is_ok_and(|metadata| metadata.len() > LARGE_FILE_BYTES)
+}
 
-        for c in line.chars() {
-            if c == '<' {
-                // タグ開始前までのテキストをプッシュ
-                if !current_text.is_empty() {
-                    spans.push(Span::styled(
-                        current_text.clone(),
-                        Style::default().fg(if in_tag { theme.comment } else { theme.fg }),
-                    ));
-                    current_text.clear();
-                }
-                in_tag = true;
-                current_text.push(c);
-            } else if c == '>' {
-                // タグ終了
-                current_text.push(c);
-                spans.push(Span::styled(
-                    current_text.clone(),
-                    Style::default().fg(theme.comment), // タグの色（コメント色と同じにして目立たなくする）
-                ));
-                current_text.clear();
-                in_tag = false;
-            } else {
-                current_text.push(c);
-            }
-        }
-        
-        // 行末に残ったテキストをプッシュ
-        if !current_text.is_empty() {
-             spans.push(Span::styled(
-                current_text,
-                Style::default().fg(if in_tag { theme.comment } else { theme.fg }),
-            ));
-        }
+enum OpenedFile {
+    Preview(Box<PreviewState>),
+    Hex(Box<HexView>),
+    Large(Box<LargeFileView>),
+}
 
-        lines.push(Line::from(spans));
+/// テキストとして読めないファイルは16進ダンプ、大きなファイルは表示する行だけを読む表示、
+/// それ以外はプレビュー（.mdは整形表示、それ以外はテキスト表示）で開く
+fn open_file(file_path: &Path, theme: &ColorScheme) -> io::Result<OpenedFile> {
+    if is_binary_file(file_path)? {
+        HexView::open(file_path).map(|view| OpenedFile::Hex(Box::new(view)))
+    } else if is_large_file(file_path) {
+        LargeFileView::open(file_path).map(|view| OpenedFile::Large(Box::new(view)))
+    } else {
+        load_preview(file_path, theme).map(|state| OpenedFile::Preview(Box::new(state)))
     }
-    Text::from(lines)
+}
+
+// コマンドの名前より後ろの文字列（例: "rename my note.md" → "my note.md"）
+fn command_arg(command_text: &str) -> &str {
+    command_text
+        .split_once(char::is_whitespace)
+        .map_or("", |(_, rest)| rest.trim())
 }
 
 // --- メインロジック ---
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
-    
-    // -m フラグのチェック
-    if let Some(pos) = args.iter().position(|arg| arg == "-m") {
-        if let Some(filename) = args.get(pos + 1) {
-            if let Err(e) = run_cui_mode(filename) {
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // サブコマンド（および旧形式の -m）はTUIを起動せずに処理する
+    let convert_args = match cli.command {
+        Some(Command::Convert(args)) => Some(args),
+        Some(Command::Batch(args)) => {
+            return match run_batch(&args) {
+                Ok(summary) => {
+                    println!(
+                        "変換: {}件 / スキップ（変更なし）: {}件 / 失敗: {}件 / コピーしたファイル: {}件",
+                        summary.converted, summary.skipped, summary.failed, summary.copied
+                    );
+                    if summary.failed > 0 {
+                        ExitCode::FAILURE
+                    } else {
+                        ExitCode::SUCCESS
+                    }
+                }
+                Err(e) => {
+                    eprintln!("エラー: {}", e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Some(Command::Check(args)) => {
+            let base = env::current_dir().unwrap_or_default();
+            return match check_links(&args.paths, args.endpoint.as_deref()) {
+                Ok(report) => {
+                    print!("{}", report.render(&base, !args.quiet));
+                    if report.problems.is_empty() {
+                        ExitCode::SUCCESS
+                    } else {
+                        ExitCode::FAILURE
+                    }
+                }
+                Err(e) => {
+                    eprintln!("エラー: {}", e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Some(Command::Serve(args)) => {
+            return match run_serve(&args) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("エラー: {}", e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        None => cli.markdown.map(ConvertArgs::from_file),
+    };
+    if let Some(args) = convert_args {
+        return match run_convert(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
                 eprintln!("{}", e);
-                std::process::exit(1);
+                e.exit_code()
             }
-            return Ok(());
-        } else {
-            eprintln!("使用法: peek -m <ファイル名>");
-            std::process::exit(1);
-        }
+        };
     }
 
-    // TUIモードの起動
-    let mut terminal = setup_terminal()?;
-    let result = run(&mut terminal);
-    restore_terminal()?;
+    // 開始ディレクトリの確認はTUIを起動する前に行う
+    let start_dir = match cli.dir {
+        Some(dir) => dir,
+        None => PathBuf::from("."),
+    };
+    let start_dir = match dunce::canonicalize(&start_dir) {
+        Ok(dir) if dir.is_dir() => dir,
+        Ok(_) => {
+            eprintln!("エラー: ディレクトリではありません: {}", start_dir.display());
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("エラー: {}: {}", start_dir.display(), e);
+            return if e.kind() == io::ErrorKind::NotFound {
+                ExitCode::from(EXIT_NOT_FOUND)
+            } else {
+                ExitCode::FAILURE
+            };
+        }
+    };
 
-    if let Err(err) = result {
-        // "quit"エラーはユーザーによる正常終了なので、エラーメッセージは表示しない
-        if err.to_string() != "quit" {
+    match run_tui(start_dir) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
             println!("エラーが発生しました: {}", err);
+            ExitCode::FAILURE
         }
     }
-    Ok(())
 }
 
-fn run_cui_mode(filename: &str) -> Result<(), Box<dyn Error>> {
-    let path = Path::new(filename);
+// --- TUIモードの起動 ---
+fn run_tui(start_dir: PathBuf) -> Result<(), Box<dyn Error>> {
+    let mut terminal = setup_terminal()?;
+    let result = run(&mut terminal, start_dir);
+    restore_terminal()?;
 
-    // 拡張子チェック
-    if path.extension().and_then(|s| s.to_str()) != Some("md") {
-        return Err("エラー: マークダウンファイル（.md）ではありません。".into());
+    match result {
+        // "quit"エラーはユーザーによる正常終了なので、エラーメッセージは表示しない
+        Err(err) if err.to_string() != "quit" => Err(err.into()),
+        _ => Ok(()),
     }
-
-    // ファイル読み込み
-    let markdown_input = fs::read_to_string(path)?;
-
-    // HTML変換
-    let parser = MarkdownParser::new_ext(&markdown_input, Options::all());
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
-
-    // クリップボードに登録
-    let mut clipboard = Clipboard::new()?;
-    clipboard.set_text(&html_output)?;
-
-    println!("HTMLへの変換とクリップボードへの登録が完了しました。");
-    println!("{}", html_output);
-
-    Ok(())
 }
 
-fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+fn run<B: Backend>(terminal: &mut Terminal<B>, start_dir: PathBuf) -> io::Result<()> {
     let mut mode = AppMode::Explorer;
-    let mut explorer_state = ExplorerState::new()?;
+    let mut explorer_state = ExplorerState::new(start_dir)?;
     let mut preview_state: Option<PreviewState> = None;
+    let mut hex_view: Option<HexView> = None;
+    let mut large_view: Option<LargeFileView> = None;
+    let mut grep_view: Option<GrepView> = None;
+    let mut back_stack: Vec<PreviewState> = Vec::new(); // リンクをたどる前の文書
     let theme = &GITHUB_DARK_THEME;
 
     loop {
+        // エクスプローラーやファイル検索で選んだファイルを、種類に合った表示で開く
+        if let Some(path) = explorer_state.open_request.take() {
+            match open_file(&path, theme) {
+                Ok(OpenedFile::Preview(state)) => {
+                    preview_state = Some(*state);
+                    mode = AppMode::Preview;
+                }
+                Ok(OpenedFile::Hex(view)) => {
+                    hex_view = Some(*view);
+                    mode = AppMode::Hex;
+                }
+                Ok(OpenedFile::Large(view)) => {
+                    large_view = Some(*view);
+                    mode = AppMode::LargeFile;
+                }
+                Err(e) => {
+                    explorer_state.set_message(format!("ファイル読み込みエラー: {}", e), true);
+                }
+            }
+        }
+        if let Some(state) = &mut preview_state {
+            state.reload_if_changed(theme);
+        }
+        explorer_state.refresh_side_preview(theme);
+        explorer_state.refresh_finder_preview(theme);
+        if let Some(view) = &mut grep_view {
+            view.poll();
+        }
+
         terminal.draw(|f| match mode {
             AppMode::Explorer => ui_explorer(f, &mut explorer_state, theme),
             AppMode::Preview => {
@@ -336,6 +1551,21 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
                     ui_preview(f, state, theme);
                 }
             }
+            AppMode::Hex => {
+                if let Some(view) = &mut hex_view {
+                    ui_hex(f, view, theme);
+                }
+            }
+            AppMode::LargeFile => {
+                if let Some(view) = &mut large_view {
+                    ui_large_file(f, view, theme);
+                }
+            }
+            AppMode::Grep => {
+                if let Some(view) = &mut grep_view {
+                    ui_grep(f, view, theme);
+                }
+            }
         })?;
 
         if !event::poll(Duration::from_millis(50))? {
@@ -350,20 +1580,94 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
             match mode {
                 AppMode::Preview => {
                     if let Some(state) = &mut preview_state {
-                        match key.code {
-                            KeyCode::Char('q') => {
-                                preview_state = None;
-                                mode = AppMode::Explorer;
+                        if key.code == KeyCode::Char('q') && !state.is_prompting() {
+                            preview_state = None;
+                            back_stack.clear();
+                            // 検索結果から開いたときは検索結果に戻る
+                            mode = if grep_view.is_some() {
+                                AppMode::Grep
+                            } else {
+                                AppMode::Explorer
+                            };
+                        } else {
+                            state.handle_key(key, theme);
+                        }
+                    }
+                    // リンク先の文書を開く・前の文書に戻る
+                    let request = preview_state.as_mut().and_then(|state| state.request.take());
+                    match request {
+                        Some(PreviewRequest::Open(path, fragment)) => match load_preview(&path, theme) {
+                            Ok(mut state) => {
+                                if let Some(fragment) = fragment {
+                                    state.jump_to_anchor(&fragment);
+                                }
+                                back_stack.extend(preview_state.replace(state));
+                            }
+                            Err(e) => {
+                                if let Some(state) = &mut preview_state {
+                                    state.status_message = Some(format!("Failed to open: {}", e));
+                                }
+                            }
+                        },
+                        Some(PreviewRequest::Back) => match back_stack.pop() {
+                            Some(state) => preview_state = Some(state),
+                            None => {
+                                if let Some(state) = &mut preview_state {
+                                    state.status_message = Some("No previous document".to_string());
+                                }
+                            }
+                        },
+                        None => {}
+                    }
+                }
+                AppMode::Hex => {
+                    if let Some(view) = &mut hex_view {
+                        if key.code == KeyCode::Char('q') && !view.is_prompting() {
+                            hex_view = None;
+                            mode = AppMode::Explorer;
+                        } else {
+                            view.handle_key(key);
+                        }
+                    }
+                }
+                AppMode::LargeFile => {
+                    if let Some(view) = &mut large_view {
+                        if key.code == KeyCode::Char('q') && !view.is_prompting() {
+                            large_view = None;
+                            mode = AppMode::Explorer;
+                        } else {
+                            view.handle_key(key);
+                        }
+                    }
+                }
+                AppMode::Grep => {
+                    if let Some(view) = &mut grep_view {
+                        if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                            grep_view = None;
+                            mode = AppMode::Explorer;
+                        } else if let GrepAction::Open = view.handle_key(key)
+                            && let Some(hit) = view.selected_hit()
+                        {
+                            match load_preview(&hit.path, theme) {
+                                Ok(mut state) => {
+                                    state.show_grep_hit(view, hit, theme);
+                                    preview_state = Some(state);
+                                    mode = AppMode::Preview;
+                                }
+                                Err(e) => view.status_message = Some(format!("Failed to open: {}", e)),
                             }
-                            KeyCode::Up | KeyCode::Char('k') => state.scroll_up(),
-                            KeyCode::Down | KeyCode::Char('j') => state.scroll_down(),
-                            KeyCode::Char('y') => state.copy_to_clipboard(), // 'y'でコピー
-                            _ => {}
                         }
                     }
                 }
                 AppMode::Explorer => {
-                    if explorer_state.in_command_mode {
+                    if let Some(op) = explorer_state.confirm.take() {
+                        explorer_state.clear_message();
+                        if key.code == KeyCode::Char('y') {
+                            explorer_state.run_file_op(op);
+                        } else {
+                            explorer_state.set_message("キャンセルしました。".to_string(), false);
+                        }
+                    } else if explorer_state.in_command_mode {
                         match key.code {
                             KeyCode::Enter => {
                                 let command_text = explorer_state.command_input.trim().to_string();
@@ -375,9 +1679,28 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
 
                                 match parts.as_slice() {
                                     ["q"] => {
-                                        return Err(io::Error::new(io::ErrorKind::Other, "quit"));
+                                        return Err(io::Error::other("quit"));
                                     }
                                     // :hp コマンドは削除されました
+                                    ["hide", condition] => {
+                                        // 値を省略したら "true" とみなす（:hide draft で下書きを隠す）
+                                        let (key, value) =
+                                            condition.split_once('=').unwrap_or((condition, "true"));
+                                        explorer_state
+                                            .hidden_by
+                                            .push((key.to_string(), value.to_string()));
+                                        explorer_state.load_entries()?;
+                                        explorer_state.set_message(
+                                            format!("非表示にする条件: {}", explorer_state.filter_label()),
+                                            false,
+                                        );
+                                    }
+                                    ["unhide"] => {
+                                        explorer_state.hidden_by.clear();
+                                        explorer_state.load_entries()?;
+                                        explorer_state
+                                            .set_message("すべてのファイルを表示します。".to_string(), false);
+                                    }
                                     ["cat", filename] => {
                                         let file_path = explorer_state.current_path.join(filename);
                                         if !file_path.is_file() {
@@ -388,13 +1711,40 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
                                             continue;
                                         }
 
-                                        match fs::read_to_string(&file_path) {
-                                            Ok(file_content) => {
-                                                preview_state = Some(PreviewState::new_text(
-                                                    &file_path,
-                                                    file_content,
-                                                    theme,
-                                                ));
+                                        // テキストとして読めないファイルは16進ダンプで表示する
+                                        if is_binary_file(&file_path).unwrap_or(false) {
+                                            match HexView::open(&file_path) {
+                                                Ok(view) => {
+                                                    hex_view = Some(view);
+                                                    mode = AppMode::Hex;
+                                                }
+                                                Err(e) => explorer_state.set_message(
+                                                    format!("ファイル読み込みエラー: {}", e),
+                                                    true,
+                                                ),
+                                            }
+                                            continue;
+                                        }
+                                        if is_large_file(&file_path) {
+                                            match LargeFileView::open(&file_path) {
+                                                Ok(view) => {
+                                                    large_view = Some(view);
+                                                    mode = AppMode::LargeFile;
+                                                }
+                                                Err(e) => explorer_state.set_message(
+                                                    format!("ファイル読み込みエラー: {}", e),
+                                                    true,
+                                                ),
+                                            }
+                                            continue;
+                                        }
+                                        match read_file(&file_path) {
+                                            Ok(decoded) => {
+                                                let encoding = decoded.label();
+                                                let mut state =
+                                                    PreviewState::new_text(&file_path, decoded.text, theme);
+                                                state.encoding = Some(encoding);
+                                                preview_state = Some(state);
                                                 mode = AppMode::Preview;
                                             }
                                             Err(e) => {
@@ -405,34 +1755,77 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
                                             }
                                         }
                                     }
-                                    ["ob", filename] => {
-                                        let file_path = explorer_state.current_path.join(filename);
-
-                                        // ファイルの存在と拡張子をチェック
-                                        if !file_path.is_file() {
-                                            explorer_state.set_message(
-                                                format!("ファイルが見つかりません: {}", filename),
-                                                true,
-                                            );
-                                        } else if file_path.extension().and_then(|s| s.to_str())
-                                            != Some("html")
-                                        {
+                                    ["grep", ..] => {
+                                        // パターンには空白を含められるので、分割する前のコマンドから取り出す
+                                        let args = command_arg(&command_text);
+                                        let (markdown_only, pattern) = match args.strip_prefix("--md") {
+                                            Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                                                (true, rest.trim())
+                                            }
+                                            _ => (false, args),
+                                        };
+                                        if pattern.is_empty() {
                                             explorer_state.set_message(
-                                                "HTMLファイルのみ開けます。".to_string(),
+                                                "検索するパターンを指定してください（例: :grep TODO）".to_string(),
                                                 true,
                                             );
-                                        } else {
-                                            // ブラウザで開く
-                                            if let Err(e) = opener::open(&file_path) {
+                                            continue;
+                                        }
+                                        match GrepView::start(&explorer_state.current_path, pattern, markdown_only) {
+                                            Ok(view) => {
+                                                grep_view = Some(view);
+                                                mode = AppMode::Grep;
+                                            }
+                                            Err(e) => {
+                                                // 構文エラーの説明は複数行になるので、最後の要約行だけを使う
+                                                let message = e.to_string();
+                                                let summary = message.lines().last().unwrap_or_default().trim();
                                                 explorer_state.set_message(
-                                                    format!("ブラウザで開けませんでした: {}", e),
+                                                    format!("正規表現が正しくありません: {}", summary.trim_start_matches("error: ")),
                                                     true,
                                                 );
-                                            } else {
-                                                explorer_state.set_message(
-                                                    format!("ブラウザで開きました: {}", filename),
-                                                    false,
+                                            }
+                                        }
+                                    }
+                                    [command @ ("mkdir" | "touch" | "rename" | "cp" | "mv" | "rm"), ..] => {
+                                        explorer_state.file_command(command, command_arg(&command_text));
+                                    }
+                                    ["undo"] => explorer_state.undo_file_op(),
+                                    ["ob", filename] => {
+                                        let file_path = explorer_state.current_path.join(filename);
+                                        explorer_state.open_in_browser(&file_path);
+                                    }
+                                    ["ob"] => {
+                                        // ファイル名を省略したら選択中のファイルを開く
+                                        if let Some(file_path) = explorer_state.selected_path().cloned() {
+                                            explorer_state.open_in_browser(&file_path);
+                                        }
+                                    }
+                                    ["check", rest @ ..] if rest.len() <= 1 => {
+                                        // 省略したら表示中のディレクトリ以下を調べる
+                                        let target = match rest {
+                                            [path] => explorer_state.current_path.join(path),
+                                            _ => explorer_state.current_path.clone(),
+                                        };
+                                        match check_links(&[target], None) {
+                                            Ok(report) => {
+                                                let mut state = PreviewState::new_text(
+                                                    &explorer_state.current_path.join("link-check.txt"),
+                                                    report.render(&explorer_state.current_path, true),
+                                                    theme,
                                                 );
+                                                state.watcher = None; // 実在するファイルの表示ではない
+                                                state.title = "Link check".to_string();
+                                                state.status_message = Some(format!(
+                                                    "{} broken link(s)",
+                                                    report.problems.len()
+                                                ));
+                                                preview_state = Some(state);
+                                                mode = AppMode::Preview;
+                                            }
+                                            Err(e) => {
+                                                explorer_state
+                                                    .set_message(format!("リンクを検査できません: {}", e), true);
                                             }
                                         }
                                     }
@@ -455,12 +1848,65 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
                             }
                             _ => {}
                         }
+                    } else if let Some(finder) = &mut explorer_state.finder {
+                        match finder.handle_key(key) {
+                            FinderAction::None => {}
+                            FinderAction::Close => explorer_state.close_finder(),
+                            FinderAction::Open(path) => {
+                                explorer_state.close_finder();
+                                explorer_state.open_request = Some(path);
+                            }
+                            FinderAction::Reveal(path) => {
+                                explorer_state.close_finder();
+                                if let Err(e) = explorer_state.reveal(&path) {
+                                    explorer_state.set_message(format!("移動できません: {}", e), true);
+                                }
+                            }
+                        }
+                    } else if explorer_state.preview_focused {
+                        // 分割表示の右側のプレビューを操作する
+                        explorer_state.clear_message();
+                        if let Some(state) = &mut explorer_state.side_preview {
+                            let leave = matches!(
+                                key.code,
+                                KeyCode::Tab | KeyCode::Esc | KeyCode::Char('q')
+                            );
+                            // 狭い右側のペインにはアウトラインとリンクの一覧を出さない
+                            let overlay = matches!(key.code, KeyCode::Char('t' | 'f'));
+                            if leave && !state.is_prompting() {
+                                explorer_state.preview_focused = false;
+                            } else if !overlay || state.is_prompting() {
+                                state.handle_key(key, theme);
+                            }
+                        } else {
+                            explorer_state.preview_focused = false;
+                        }
                     } else {
                         explorer_state.clear_message(); // 操作時にメッセージをクリア
                         match key.code {
                             KeyCode::Char(':') => {
                                 explorer_state.in_command_mode = true;
                             }
+                            KeyCode::Char('s') => explorer_state.toggle_split_view(),
+                            // 選択中のファイルの操作（名前を入れた状態でコマンドモードを始める）
+                            KeyCode::Char('a') => explorer_state.start_command("touch", false),
+                            KeyCode::Char('A') => explorer_state.start_command("mkdir", false),
+                            KeyCode::Char('r') => explorer_state.start_command("rename", true),
+                            KeyCode::Char('c') => explorer_state.start_command("cp", true),
+                            KeyCode::Char('m') => explorer_state.start_command("mv", true),
+                            KeyCode::Char('d') => explorer_state.file_command("rm", ""),
+                            KeyCode::Char('u') => explorer_state.undo_file_op(),
+                            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                explorer_state.finder = Some(FileFinder::new(&explorer_state.current_path));
+                            }
+                            KeyCode::Char('o') => {
+                                if let Some(file_path) = explorer_state.selected_path().cloned() {
+                                    explorer_state.open_in_browser(&file_path);
+                                }
+                            }
+                            KeyCode::Tab => {
+                                explorer_state.preview_focused = explorer_state.side_preview.is_some();
+                            }
                             KeyCode::Down | KeyCode::Char('j') => explorer_state.next(),
                             KeyCode::Up | KeyCode::Char('k') => explorer_state.previous(),
                             KeyCode::Left | KeyCode::Char('h') | KeyCode::Backspace => {
@@ -470,66 +1916,18 @@ fn run<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
                                 }
                             }
                             KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => {
-                                if let Some(selected_index) = explorer_state.list_state.selected() {
-                                    if let Some(selected_path) =
+                                if let Some(selected_index) = explorer_state.list_state.selected()
+                                    && let Some(selected_path) =
                                         explorer_state.entries.get(selected_index)
-                                    {
-                                        let selected_path = selected_path.clone();
-                                        if selected_path.is_dir() {
-                                            // ディレクトリなら移動
-                                            explorer_state.current_path =
-                                                dunce::canonicalize(selected_path)?;
-                                            explorer_state.load_entries()?;
-                                        } else {
-                                            // ファイルの場合
-                                            if selected_path.extension().and_then(|s| s.to_str())
-                                                == Some("md")
-                                            {
-                                                // .mdファイルならHTMLに変換してプレビュー画面で表示する
-                                                match fs::read_to_string(&selected_path) {
-                                                    Ok(markdown_input) => {
-                                                        let parser = MarkdownParser::new_ext(
-                                                            &markdown_input,
-                                                            Options::all(),
-                                                        );
-                                                        let mut html_output = String::new();
-                                                        html::push_html(&mut html_output, parser);
-
-                                                        preview_state =
-                                                            Some(PreviewState::new_html(
-                                                                &selected_path,
-                                                                html_output,
-                                                                theme,
-                                                            ));
-                                                        mode = AppMode::Preview;
-                                                    }
-                                                    Err(e) => {
-                                                        explorer_state.set_message(
-                                                            format!("ファイル読み込みエラー: {}", e),
-                                                            true,
-                                                        );
-                                                    }
-                                                }
-                                            } else {
-                                                // .md以外のファイルはプレーンテキストとして開く
-                                                match fs::read_to_string(&selected_path) {
-                                                    Ok(file_content) => {
-                                                        preview_state = Some(PreviewState::new_text(
-                                                            &selected_path,
-                                                            file_content,
-                                                            theme,
-                                                        ));
-                                                        mode = AppMode::Preview;
-                                                    }
-                                                    Err(e) => {
-                                                        explorer_state.set_message(
-                                                            format!("ファイル読み込みエラー: {}", e),
-                                                            true,
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        }
+                                {
+                                    let selected_path = selected_path.clone();
+                                    if selected_path.is_dir() {
+                                        // ディレクトリなら移動
+                                        explorer_state.current_path =
+                                            dunce::canonicalize(selected_path)?;
+                                        explorer_state.load_entries()?;
+                                    } else {
+                                        explorer_state.open_request = Some(selected_path);
                                     }
                                 }
                             }
@@ -550,6 +1948,17 @@ fn ui_explorer(f: &mut Frame, state: &mut ExplorerState, theme: &ColorScheme) {
         .constraints([Constraint::Min(0), Constraint::Length(2)].as_ref())
         .split(f.size());
 
+    // 分割表示では左にファイル一覧、右にプレビューを並べる
+    let (list_area, side_area) = if state.split_view {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(chunks[0]);
+        (panes[0], Some(panes[1]))
+    } else {
+        (chunks[0], None)
+    };
+
     let items: Vec<ListItem> = state
         .entries
         .iter()
@@ -577,8 +1986,20 @@ fn ui_explorer(f: &mut Frame, state: &mut ExplorerState, theme: &ColorScheme) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(state.current_path.to_string_lossy().to_string())
-                .style(Style::default().fg(theme.fg).bg(theme.bg)),
+                .title(if state.hidden_by.is_empty() {
+                    state.current_path.to_string_lossy().to_string()
+                } else {
+                    format!(
+                        "{} [hide: {}]",
+                        state.current_path.to_string_lossy(),
+                        state.filter_label()
+                    )
+                })
+                .style(Style::default().fg(theme.fg).bg(theme.bg))
+                .border_style(pane_border_style(
+                    state.split_view && !state.preview_focused,
+                    theme,
+                )),
         )
         .highlight_style(
             Style::default()
@@ -588,15 +2009,28 @@ fn ui_explorer(f: &mut Frame, state: &mut ExplorerState, theme: &ColorScheme) {
         )
         .highlight_symbol(">> ");
 
-    f.render_stateful_widget(list, chunks[0], &mut state.list_state);
+    f.render_stateful_widget(list, list_area, &mut state.list_state);
+
+    if let Some(side_area) = side_area {
+        ui_side_preview(f, side_area, state, theme);
+    }
+    if state.finder.is_some() {
+        ui_finder(f, chunks[0], state, theme);
+    }
 
     let status_bar_style = Style::default().fg(theme.fg).bg(theme.bg);
     let status_text = if state.in_command_mode {
         format!(":{}", state.command_input)
+    } else if state.finder.is_some() {
+        "Type to filter | ↑/↓: Select | Enter: Open | Tab: Go to directory | Ctrl-u: Clear | Esc: Close".to_string()
     } else if let Some(msg) = &state.status_message {
         msg.clone()
+    } else if state.preview_focused {
+        "j/k: Scroll | Ctrl-d/u: Half page | gg/G: Top/Bottom | v: Toggle view | y: Copy | Tab/Esc: Back to list".to_string()
+    } else if state.split_view {
+        "j/k: Move | Tab: Focus preview | s: Close split | Enter: Preview | Ctrl-p: Find file | o: Open in browser | :<cmd>: Command".to_string()
     } else {
-        "j/k: Move | Enter: View HTML Source | :<cmd>: Command (:cat, :ob, :q)".to_string()
+        "j/k: Move | Enter: Preview | s: Split view | Ctrl-p: Find file | a/A: New file/dir | r/c/m/d: Rename/Copy/Move/Delete | u: Undo | o: Open in browser | :<cmd>: Command (:cat, :ob, :q)".to_string()
     };
     
     let status_color = if state.is_error {
@@ -612,6 +2046,56 @@ fn ui_explorer(f: &mut Frame, state: &mut ExplorerState, theme: &ColorScheme) {
     f.render_widget(status_bar, chunks[1]);
 }
 
+// 分割表示ではフォーカスのあるペインの枠を強調する
+fn pane_border_style(focused: bool, theme: &ColorScheme) -> Style {
+    if focused {
+        Style::default().fg(theme.link)
+    } else {
+        Style::default().fg(theme.border)
+    }
+}
+
+fn ui_side_preview(f: &mut Frame, area: Rect, state: &mut ExplorerState, theme: &ColorScheme) {
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().fg(theme.fg).bg(theme.bg))
+        .border_style(pane_border_style(state.preview_focused, theme));
+
+    let Some(preview) = &mut state.side_preview else {
+        // ディレクトリや読み込めないファイルはプレビューしない
+        let placeholder = Paragraph::new("No preview")
+            .style(Style::default().fg(theme.comment))
+            .alignment(Alignment::Center)
+            .block(block);
+        f.render_widget(placeholder, area);
+        return;
+    };
+
+    let file_name = preview
+        .file_path
+        .file_name()
+        .map_or_else(|| preview.title.clone(), |s| s.to_string_lossy().to_string());
+    let inner = block.inner(area);
+    preview.set_viewport(inner.width, inner.height);
+
+    // 入力中のプロンプト、一時メッセージ、位置の順に下枠へ表示する
+    let bottom = if let Some(input) = &preview.line_prompt {
+        format!(":{}", input)
+    } else if preview.search_prompt.is_some() {
+        format!("/{}{} {}", preview.search.query, preview.search.mode_label(), preview.search.counter())
+    } else if let Some(msg) = &preview.status_message {
+        msg.clone()
+    } else {
+        format!("{}{}", preview.pending_keys(), preview.position_label())
+    };
+    block = block
+        .title(format!("{}{}", preview.view_label(), file_name))
+        .title_bottom(Line::from(bottom).alignment(Alignment::Right));
+
+    let paragraph = Paragraph::new(preview.visible_text()).block(block);
+    f.render_widget(paragraph, area);
+}
+
 fn ui_preview(f: &mut Frame, state: &mut PreviewState, theme: &ColorScheme) {
     // Create a layout with a main area and a footer
     let chunks = Layout::default()
@@ -622,18 +2106,289 @@ fn ui_preview(f: &mut Frame, state: &mut PreviewState, theme: &ColorScheme) {
         ])
         .split(f.size());
 
+    // アウトラインペインを開いているときは左側に表示する
+    let text_area = if state.outline_pane.is_some() {
+        let width = (chunks[0].width / 3).min(32);
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(width), Constraint::Min(0)])
+            .split(chunks[0]);
+        ui_outline(f, panes[0], state, theme);
+        panes[1]
+    } else {
+        chunks[0]
+    };
+
     // Main content paragraph without a block/border
-    let paragraph = Paragraph::new(state.content.clone())
+    state.set_viewport(text_area.width, text_area.height);
+    let paragraph = Paragraph::new(state.visible_text())
+        .style(Style::default().fg(theme.fg).bg(theme.bg));
+    f.render_widget(paragraph, text_area);
+    if state.link_hints.is_some() {
+        ui_link_hints(f, text_area, state, theme);
+    }
+
+    // 行番号の入力中はフッターを入力欄にする
+    if let Some(input) = &state.line_prompt {
+        let prompt = Paragraph::new(format!(":{}", input))
+            .style(Style::default().fg(theme.fg).bg(theme.bg));
+        f.render_widget(prompt, chunks[1]);
+        return;
+    }
+
+    // 検索語の入力中はフッターを入力欄にする
+    if state.search_prompt.is_some() {
+        let search = &state.search;
+        let (result, color) = match &search.error {
+            Some(e) => (format!("Invalid pattern: {}", e), Color::Red),
+            None if search.query.is_empty() => (String::new(), theme.comment),
+            None => (search.counter(), theme.comment),
+        };
+        let prompt = Line::from(vec![
+            Span::styled(format!("/{}", search.query), Style::default().fg(theme.fg)),
+            Span::styled(search.mode_label(), Style::default().fg(theme.comment)),
+            Span::raw(" "),
+            Span::styled(result, Style::default().fg(color)),
+        ]);
+        f.render_widget(Paragraph::new(prompt).style(Style::default().bg(theme.bg)), chunks[1]);
+        return;
+    }
+
+    // Footer
+    let default_msg = if let Some(hints) = &state.link_hints {
+        &format!("Follow link: {}_ | ↑/↓ + Enter: Select | Esc: Cancel", hints.typed)
+    } else if state.outline_pane.as_ref().is_some_and(|pane| pane.focused) {
+        "j/k: Move | Enter: Jump | h/l: Fold | Tab: Focus text | t: Close outline"
+    } else if state.rendered.is_some() {
+        "Press 'q' to close | 'y' to copy | 'v' to toggle view | 't' for outline | 'f' to follow a link | 'c' for stats | '/' to search"
+    } else {
+        "Press 'q' to close | 'y' to copy | 'c' for stats | '/' to search"
+    };
+    let msg = state.status_message.as_deref().unwrap_or(default_msg);
+    let search_info = if state.search.query.is_empty() {
+        String::new()
+    } else {
+        format!("{} /{} | ", state.search.counter(), state.search.query)
+    };
+    let count = if state.show_stats {
+        // 表示中の節の文字数も添える
+        let section = section_at(&state.heading_lines(), state.top_line())
+            .and_then(|i| state.stats.sections.get(i))
+            .map(|section| format!("§ {} chars | ", section.chars))
+            .unwrap_or_default();
+        format!("{}{}", section, stats_label(&state.stats.total))
+    } else {
+        format!("{} chars", state.char_count)
+    };
+    let encoding = state
+        .encoding
+        .as_ref()
+        .map(|encoding| format!(" | {}", encoding))
+        .unwrap_or_default();
+    let footer_text = format!(
+        "{}{}{}{}{} | {} | {} | {}",
+        state.pending_keys(),
+        search_info,
+        state.view_label(),
+        state.title,
+        encoding,
+        state.position_label(),
+        count,
+        msg
+    );
+    let footer = Paragraph::new(footer_text)
+        .style(Style::default().fg(theme.comment).bg(theme.bg))
+        .alignment(Alignment::Right);
+    f.render_widget(footer, chunks[1]);
+}
+
+fn ui_hex(f: &mut Frame, view: &mut HexView, theme: &ColorScheme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(f.size());
+
+    view.set_viewport(chunks[0].height);
+    let paragraph = Paragraph::new(view.visible_lines(theme))
+        .style(Style::default().fg(theme.fg).bg(theme.bg));
+    f.render_widget(paragraph, chunks[0]);
+
+    // 位置・検索語の入力中はフッターを入力欄にする
+    if let Some((kind, input)) = &view.prompt {
+        let prefix = match kind {
+            HexPrompt::Offset => ":",
+            HexPrompt::Search => "/",
+        };
+        let prompt = Paragraph::new(format!("{}{}", prefix, input))
+            .style(Style::default().fg(theme.fg).bg(theme.bg));
+        f.render_widget(prompt, chunks[1]);
+        return;
+    }
+
+    let default_msg = "Press 'q' to close | ':' to jump to offset | '/' to search bytes | 'n'/'N' for next/prev";
+    let msg = view.status_message.as_deref().unwrap_or(default_msg);
+    let search_info = if view.query.is_empty() {
+        String::new()
+    } else {
+        format!("/{} | ", view.query)
+    };
+    let footer_text = format!(
+        "{}{}[HEX] {} | {} | {} bytes | {}",
+        view.pending_keys(),
+        search_info,
+        view.title,
+        view.position_label(),
+        view.len,
+        msg
+    );
+    let footer = Paragraph::new(footer_text)
+        .style(Style::default().fg(theme.comment).bg(theme.bg))
+        .alignment(Alignment::Right);
+    f.render_widget(footer, chunks[1]);
+}
+
+fn ui_grep(f: &mut Frame, view: &mut GrepView, theme: &ColorScheme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(CONTEXT_LINES as u16 * 2 + 3), // 選択中の一致箇所の前後の行
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    // 表示しきれる分だけ作る（結果は数千件になることがある）
+    view.set_viewport(chunks[0].height);
+    let match_style = Style::default().bg(theme.match_bg);
+    let items: Vec<ListItem> = view
+        .hits
+        .iter()
+        .skip(view.top)
+        .take(chunks[0].height as usize)
+        .map(|hit| {
+            let mut spans = vec![
+                Span::styled(hit.relative.clone(), Style::default().fg(theme.link)),
+                Span::styled(format!(":{}: ", hit.line + 1), Style::default().fg(theme.comment)),
+            ];
+            // 行頭の字下げは一覧では省く
+            let indent = hit.text.len() - hit.text.trim_start().len();
+            spans.extend(grep_line_spans(&hit.text, indent, &hit.ranges, match_style, theme).spans);
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
         .style(Style::default().fg(theme.fg).bg(theme.bg))
-        .wrap(Wrap { trim: false })
-        .scroll((state.scroll, 0));
+        .highlight_style(Style::default().bg(theme.selection_bg));
+    let mut list_state = ListState::default()
+        .with_selected((!view.hits.is_empty()).then(|| view.selected - view.top));
+    f.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .style(Style::default().fg(theme.fg).bg(theme.bg))
+        .border_style(Style::default().fg(theme.border));
+    let context = match view.selected_hit() {
+        Some(hit) => {
+            let first = hit.line - hit.before.len();
+            let number = |n: usize| Span::styled(format!("{:>6} ", n + 1), Style::default().fg(theme.comment));
+            let mut lines: Vec<Line> = hit
+                .before
+                .iter()
+                .enumerate()
+                .map(|(i, line)| Line::from(vec![number(first + i), Span::raw(line.clone())]))
+                .collect();
+            let mut current = vec![number(hit.line)];
+            current.extend(grep_line_spans(&hit.text, 0, &hit.ranges, match_style, theme).spans);
+            lines.push(Line::from(current));
+            lines.extend(
+                hit.after
+                    .iter()
+                    .enumerate()
+                    .map(|(i, line)| Line::from(vec![number(hit.line + 1 + i), Span::raw(line.clone())])),
+            );
+            Paragraph::new(lines).block(block.title(hit.relative.clone()))
+        }
+        None => Paragraph::new(if view.is_searching() { "Searching..." } else { "No matches" })
+            .style(Style::default().fg(theme.comment))
+            .alignment(Alignment::Center)
+            .block(block),
+    };
+    f.render_widget(context, chunks[1]);
+
+    let default_msg = "Press 'q' to close | Enter to open | 'j'/'k' to move";
+    let msg = view.status_message.as_deref().unwrap_or(default_msg);
+    let progress = if view.is_searching() {
+        " (searching)".to_string()
+    } else if view.hits.len() >= MAX_HITS {
+        format!(" (stopped at {} hits)", MAX_HITS)
+    } else {
+        String::new()
+    };
+    let footer_text = format!(
+        "{}[GREP] /{}/{} | {} | {} files{} | {}",
+        view.pending_keys(),
+        view.pattern,
+        if view.markdown_only { " *.md" } else { "" },
+        view.position_label(),
+        view.files_searched,
+        progress,
+        msg
+    );
+    let footer = Paragraph::new(footer_text)
+        .style(Style::default().fg(theme.comment).bg(theme.bg))
+        .alignment(Alignment::Right);
+    f.render_widget(footer, chunks[2]);
+}
+
+// 検索結果の行の`skip`バイト目以降を、一致箇所を強調して表示する
+fn grep_line_spans(text: &str, skip: usize, ranges: &[(usize, usize)], style: Style, theme: &ColorScheme) -> Line<'static> {
+    let line = Line::from(Span::styled(text[skip..].to_string(), Style::default().fg(theme.fg)));
+    ranges.iter().fold(line, |line, &(start, end)| {
+        restyle_range(&line, start.saturating_sub(skip), end.saturating_sub(skip), style)
+    })
+}
+
+fn ui_large_file(f: &mut Frame, view: &mut LargeFileView, theme: &ColorScheme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(f.size());
+
+    view.set_viewport(chunks[0].width, chunks[0].height);
+    let paragraph = Paragraph::new(view.visible_text(theme))
+        .style(Style::default().fg(theme.fg).bg(theme.bg));
     f.render_widget(paragraph, chunks[0]);
 
-    // Footer
-    let msg = state.status_message.as_deref().unwrap_or("Press 'q' to close | 'y' to copy");
+    // 行番号・検索語の入力中はフッターを入力欄にする
+    let prompt = if let Some(input) = &view.line_prompt {
+        Some(format!(":{}", input))
+    } else if view.search_prompt.is_some() {
+        Some(format!("/{}{}", view.search.query, view.search.mode_label()))
+    } else {
+        None
+    };
+    if let Some(prompt) = prompt {
+        let prompt = Paragraph::new(prompt).style(Style::default().fg(theme.fg).bg(theme.bg));
+        f.render_widget(prompt, chunks[1]);
+        return;
+    }
+
+    let default_msg = "Press 'q' to close | ':' to jump to a line | '/' to search";
+    let msg = view.status_message.as_deref().unwrap_or(default_msg);
+    let search_info = match view.search_progress() {
+        _ if view.search.query.is_empty() => String::new(),
+        Some(percent) => format!("/{} (searching {}%) | ", view.search.query, percent),
+        None => format!("/{} | ", view.search.query),
+    };
     let footer_text = format!(
-        "{} | {} chars | {}",
-        state.title, state.char_count, msg
+        "{}{}[Large] {} | {} | {} | {} bytes | {}",
+        view.pending_keys(),
+        search_info,
+        view.title,
+        view.encoding_label,
+        view.position_label(),
+        view.len,
+        msg
     );
     let footer = Paragraph::new(footer_text)
         .style(Style::default().fg(theme.comment).bg(theme.bg))
@@ -641,6 +2396,213 @@ fn ui_preview(f: &mut Frame, state: &mut PreviewState, theme: &ColorScheme) {
     f.render_widget(footer, chunks[1]);
 }
 
+// 本文の文字数（全角・半角の内訳）、原稿用紙の枚数、単語数、文の数、読む時間
+fn stats_label(stats: &TextStats) -> String {
+    format!(
+        "{} chars (full {} / half {}) | {:.1} pages (400) | {} words | {} sentences | ~{} min",
+        stats.chars,
+        stats.full_width,
+        stats.half_width(),
+        stats.manuscript_pages(),
+        stats.words,
+        stats.sentences,
+        stats.reading_minutes()
+    )
+}
+
+// 見出しを階層ごとに字下げして並べ、先頭に表示中の節を強調する
+fn ui_outline(f: &mut Frame, area: Rect, state: &PreviewState, theme: &ColorScheme) {
+    let Some(pane) = &state.outline_pane else {
+        return;
+    };
+    let current = section_at(&state.heading_lines(), state.top_line());
+    let visible = pane.visible(&state.outline);
+    let items: Vec<ListItem> = visible
+        .iter()
+        .map(|&i| {
+            let entry = &state.outline[i];
+            let has_children = state.outline.get(i + 1).is_some_and(|next| next.level > entry.level);
+            let marker = match (has_children, pane.collapsed.contains(&i)) {
+                (false, _) => "  ",
+                (true, false) => "▾ ",
+                (true, true) => "▸ ",
+            };
+            let color = if current == Some(i) { theme.link } else { theme.fg };
+            let label = format!("{}{}{}", "  ".repeat(entry.level - 1), marker, entry.title);
+            // 統計の表示中は、見出しごとの文字数（配下の見出しを含む）を右端に並べる
+            let Some(section) = state.stats.sections.get(i).filter(|_| state.show_stats) else {
+                return ListItem::new(Span::styled(label, Style::default().fg(color)));
+            };
+            let count = section.chars.to_string();
+            let padding = (area.width as usize).saturating_sub(label.width() + count.width() + 1);
+            ListItem::new(Line::from(vec![
+                Span::styled(label, Style::default().fg(color)),
+                Span::raw(" ".repeat(padding)),
+                Span::styled(count, Style::default().fg(theme.comment)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::RIGHT)
+                .title("Outline")
+                .style(Style::default().fg(theme.fg).bg(theme.bg))
+                .border_style(pane_border_style(pane.focused, theme)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut list_state = ListState::default().with_selected(visible.iter().position(|&i| i == pane.selected));
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+// ファイル検索を一覧の上に重ねて表示する。左に入力欄と候補、右に選択中のファイルのプレビュー
+fn ui_finder(f: &mut Frame, area: Rect, state: &mut ExplorerState, theme: &ColorScheme) {
+    let Some(finder) = &state.finder else {
+        return;
+    };
+    let width = (area.width * 9 / 10).max(area.width.min(40));
+    let height = (area.height * 9 / 10).max(area.height.min(10));
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, popup);
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(popup);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            "Find file {}/{}{}",
+            finder.matches.len(),
+            finder.files.len(),
+            if finder.is_indexing() { " (indexing)" } else { "" }
+        ))
+        .style(Style::default().fg(theme.fg).bg(theme.bg))
+        .border_style(Style::default().fg(theme.link));
+    let inner = block.inner(panes[0]);
+    f.render_widget(block, panes[0]);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled("> ", Style::default().fg(theme.link)),
+            Span::raw(finder.query.clone()),
+        ])),
+        rows[0],
+    );
+
+    // 表示しきれる分だけ作る（候補は数万件になることがある）
+    let visible = rows[1].height as usize;
+    let first = finder.selected.saturating_sub(visible.saturating_sub(1));
+    let items: Vec<ListItem> = finder
+        .matches
+        .iter()
+        .skip(first)
+        .take(visible)
+        .map(|&i| {
+            let file = &finder.files[i];
+            let positions = finder.match_positions(file);
+            let spans: Vec<Span> = file
+                .chars()
+                .enumerate()
+                .map(|(n, c)| {
+                    let style = if positions.contains(&n) {
+                        Style::default().fg(theme.link).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(theme.fg)
+                    };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect();
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items).highlight_style(Style::default().bg(theme.selection_bg));
+    let mut list_state = ListState::default()
+        .with_selected((!finder.matches.is_empty()).then(|| finder.selected - first));
+    f.render_stateful_widget(list, rows[1], &mut list_state);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().fg(theme.fg).bg(theme.bg))
+        .border_style(Style::default().fg(theme.border));
+    let Some(preview) = &mut state.finder_preview else {
+        let placeholder = Paragraph::new("No preview")
+            .style(Style::default().fg(theme.comment))
+            .alignment(Alignment::Center)
+            .block(block);
+        f.render_widget(placeholder, panes[1]);
+        return;
+    };
+    let inner = block.inner(panes[1]);
+    preview.set_viewport(inner.width, inner.height);
+    let paragraph = Paragraph::new(preview.visible_text()).block(block.title(preview.title.clone()));
+    f.render_widget(paragraph, panes[1]);
+}
+
+// リンクの一覧を本文の上に重ねて表示する。入力済みのラベルに一致しないリンクは暗くする
+fn ui_link_hints(f: &mut Frame, area: Rect, state: &PreviewState, theme: &ColorScheme) {
+    let Some(hints) = &state.link_hints else {
+        return;
+    };
+    let matching = hints.matching();
+    let items: Vec<ListItem> = state
+        .links
+        .iter()
+        .zip(&hints.labels)
+        .enumerate()
+        .map(|(i, (link, label))| {
+            let (label_color, text_color) = if matching.contains(&i) {
+                (theme.link, theme.fg)
+            } else {
+                (theme.comment, theme.comment)
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:>2} ", label),
+                    Style::default().fg(label_color).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("{} ", link.text), Style::default().fg(text_color)),
+                Span::styled(link.url.clone(), Style::default().fg(theme.comment)),
+            ]))
+        })
+        .collect();
+
+    let width = area.width.saturating_sub(4).min(80);
+    let height = (items.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Links")
+                .style(Style::default().fg(theme.fg).bg(theme.bg))
+                .border_style(Style::default().fg(theme.link)),
+        )
+        .highlight_style(Style::default().bg(theme.selection_bg));
+    let mut list_state = ListState::default().with_selected(Some(hints.selected));
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut list_state);
+}
+
 // --- ターミナル設定 ---
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>, Box<dyn Error>> {
     let mut stdout = stdout();
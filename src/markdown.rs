@@ -0,0 +1,753 @@
+// --- Markdownの変換・描画 ---
+//
+// pulldown-cmarkのイベント列を直接たどり、HTMLを経由せずに
+// ratatuiの`Text`を組み立てる。
+
+use std::path::Path;
+
+use pulldown_cmark::{
+    html, Alignment as ColumnAlignment, CodeBlockKind, Event, HeadingLevel, Options,
+    Parser as MarkdownParser, Tag, TagEnd,
+};
+use ratatui::prelude::*;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    export::escape_html,
+    front_matter::{split_front_matter, FrontMatter},
+    outline::{toc_html, OutlineEntry},
+    slug::assign_heading_ids,
+    syntax::{highlight_lines, syntax_for_lang},
+    ColorScheme,
+};
+
+/// 水平線として描画する罫線の長さ
+const RULE_WIDTH: usize = 40;
+
+/// HTMLに変換するときのオプション
+#[derive(Clone, Copy, Default)]
+pub struct HtmlOptions {
+    pub toc: bool,     // 本文の先頭に目次を入れる
+    pub anchors: bool, // 見出しの横に"¶"のリンクを付ける
+}
+
+/// 拡張子が".md"のファイルか（"NOTE.MD"のように大文字でもよい）
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+}
+
+/// MarkdownをHTML断片に変換する
+pub fn markdown_to_html(markdown_input: &str) -> String {
+    events_to_html(markdown_events(markdown_input))
+}
+
+/// Markdownをイベント列に分解する（プレビューと同じ拡張記法を有効にする）。
+/// フロントマターは本文ではないので取り除いておく。フロントマターとして読めなかった
+/// `---`のブロックは本文に残すので、pulldown-cmarkのメタデータブロックは有効にしない
+pub fn markdown_events(markdown_input: &str) -> MarkdownParser<'_> {
+    let (_, body) = split_front_matter(markdown_input);
+    let options = Options::all()
+        - Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        - Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS;
+    MarkdownParser::new_ext(body, options)
+}
+
+/// 各行の先頭のバイト位置。イベントの位置から行番号（0始まり）を
+/// `line_starts.partition_point(|&start| start <= offset) - 1`で求めるのに使う
+pub fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// イベント列をHTML断片に変換する。リンク先の書き換えなどはイベント列の段階で行う
+pub fn events_to_html<'a>(events: impl Iterator<Item = Event<'a>>) -> String {
+    render_html(events, HtmlOptions::default())
+}
+
+/// 見出しにGitHubと同じ規則のidを付けてHTML断片に変換する。
+/// `push_html`は見出しのidを作らないので、イベント列に書き込んでから渡す
+pub fn render_html<'a>(events: impl Iterator<Item = Event<'a>>, options: HtmlOptions) -> String {
+    let mut events = events.collect::<Vec<_>>();
+    let headings = assign_heading_ids(&mut events);
+    if options.anchors {
+        // 後ろから挿入して、まだ挿入していない見出しの位置がずれないようにする
+        for heading in headings.iter().rev() {
+            let anchor = format!(
+                " <a class=\"anchor\" href=\"#{}\" aria-hidden=\"true\">¶</a>",
+                escape_html(&heading.id)
+            );
+            events.insert(heading.end, Event::InlineHtml(anchor.into()));
+        }
+    }
+
+    let mut html_output = if options.toc { toc_html(&headings) } else { String::new() };
+    html::push_html(&mut html_output, events.into_iter());
+    html_output
+}
+
+/// 整形表示したMarkdownと、その中の見出し・リンクの位置
+pub struct RenderedMarkdown {
+    pub text: Text<'static>,
+    pub outline: Vec<OutlineEntry>,
+    pub links: Vec<LinkEntry>,
+    pub source_lines: Vec<SourceLine>,
+}
+
+/// 元のMarkdownの行の文字列が、整形表示のどこに描画されたか
+/// （表は全体を描画してから並べるので、表の先頭の位置になる）
+pub struct SourceLine {
+    pub source: usize, // 元のMarkdownでの行番号（フロントマターも含めて数える）
+    pub line: usize,   // 整形表示での行番号
+    pub column: usize, // 整形表示の行の中のバイト位置
+}
+
+/// 整形表示中のリンク
+pub struct LinkEntry {
+    pub url: String,
+    pub text: String, // リンクの文字列
+    pub line: usize,  // 整形表示での行番号
+}
+
+/// Markdownを装飾付きのテキストとして描画する
+pub fn render_markdown(markdown_input: &str, theme: &ColorScheme) -> RenderedMarkdown {
+    let mut renderer = Renderer::new(theme);
+    let (front_matter, body) = split_front_matter(markdown_input);
+    // イベントの位置は本文の先頭からのバイト位置なので、フロントマターの分をずらして行番号にする
+    let body_start = markdown_input.len() - body.len();
+    if let Some(front_matter) = front_matter
+        && !front_matter.fields.is_empty()
+    {
+        renderer.render_front_matter(&front_matter, &markdown_input[..body_start]);
+    }
+    let line_starts = line_starts(markdown_input);
+    for (event, range) in markdown_events(markdown_input).into_offset_iter() {
+        renderer.source_line = line_starts.partition_point(|&start| start <= body_start + range.start) - 1;
+        renderer.handle(event);
+    }
+    let mut rendered = renderer.finish();
+    // 見出しのidはHTMLへの変換と同じ方法で付ける（"#見出し"のリンクをたどるため）
+    let mut events = markdown_events(markdown_input).collect::<Vec<_>>();
+    for (entry, heading) in rendered.outline.iter_mut().zip(assign_heading_ids(&mut events)) {
+        entry.id = heading.id;
+    }
+    rendered
+}
+
+// コードブロックの中身をまとめて描画するためのバッファ
+struct CodeBlockBuffer {
+    lang: Option<String>,
+    text: String,
+    source_line: usize, // コードの1行目の元のMarkdownでの行番号
+}
+
+// 表は列幅を揃えるため、全セルを集めてから描画する
+struct TableBuffer {
+    alignments: Vec<ColumnAlignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    header_rows: usize,
+    current_row: Vec<Vec<Span<'static>>>,
+    current_cell: Vec<Span<'static>>,
+}
+
+// ネストしたリストの1段分
+struct ListLevel {
+    next_number: Option<u64>,
+    marker_width: usize,
+}
+
+struct Renderer<'t> {
+    theme: &'t ColorScheme,
+    lines: Vec<Line<'static>>,
+    spans: Vec<Span<'static>>,  // 組み立て中の行
+    inline_styles: Vec<Style>,  // 強調やリンクなどのインライン装飾
+    lists: Vec<ListLevel>,
+    pending_marker: Option<String>, // 次の行頭に付けるリストマーカー
+    quote_depth: usize,
+    heading: Option<HeadingLevel>,
+    outline: Vec<OutlineEntry>,
+    links: Vec<LinkEntry>,
+    in_link: bool,
+    code_block: Option<CodeBlockBuffer>,
+    table: Option<TableBuffer>,
+    source_line: usize,            // 処理中のイベントの元のMarkdownでの行番号
+    source_lines: Vec<SourceLine>,
+}
+
+impl<'t> Renderer<'t> {
+    fn new(theme: &'t ColorScheme) -> Self {
+        Self {
+            theme,
+            lines: Vec::new(),
+            spans: Vec::new(),
+            inline_styles: Vec::new(),
+            lists: Vec::new(),
+            pending_marker: None,
+            quote_depth: 0,
+            heading: None,
+            outline: Vec::new(),
+            links: Vec::new(),
+            in_link: false,
+            code_block: None,
+            table: None,
+            source_line: 0,
+            source_lines: Vec::new(),
+        }
+    }
+
+    fn finish(mut self) -> RenderedMarkdown {
+        self.flush_line();
+        // 末尾の空行は取り除く
+        while self.lines.last().is_some_and(|line| line.width() == 0) {
+            self.lines.pop();
+        }
+        RenderedMarkdown {
+            text: Text::from(self.lines),
+            outline: self.outline,
+            links: self.links,
+            source_lines: self.source_lines,
+        }
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start_tag(tag),
+            Event::End(tag) => self.end_tag(tag),
+            Event::Text(text) => {
+                self.push_title_text(&text);
+                if let Some(code_block) = &mut self.code_block {
+                    code_block.text.push_str(&text);
+                } else {
+                    self.mark_source();
+                    self.push_text(&text, self.current_style());
+                }
+            }
+            Event::Code(code) => {
+                self.mark_source();
+                self.push_title_text(&code);
+                let style = self
+                    .current_style()
+                    .fg(self.theme.code_fg)
+                    .bg(self.theme.code_bg);
+                self.push_span(Span::styled(code.into_string(), style));
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => {
+                self.mark_source();
+                let style = Style::default().fg(self.theme.comment);
+                self.push_text(&raw, style);
+            }
+            Event::FootnoteReference(label) => {
+                let style = Style::default().fg(self.theme.link);
+                self.push_span(Span::styled(format!("[^{}]", label), style));
+            }
+            Event::SoftBreak => self.push_span(Span::styled(" ", self.current_style())),
+            Event::HardBreak => self.flush_line(),
+            Event::Rule => {
+                self.flush_line();
+                let style = Style::default().fg(self.theme.border);
+                self.push_span(Span::styled("─".repeat(RULE_WIDTH), style));
+                self.end_block();
+            }
+            Event::TaskListMarker(checked) => {
+                let marker = if checked { "[x] " } else { "[ ] " };
+                let style = Style::default().fg(self.theme.comment);
+                self.push_span(Span::styled(marker, style));
+            }
+        }
+    }
+
+    fn start_tag(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.flush_line(),
+            Tag::Heading { level, .. } => {
+                self.flush_line();
+                self.heading = Some(level);
+                self.outline.push(OutlineEntry {
+                    level: heading_rank(level),
+                    title: String::new(),
+                    line: self.lines.len(),
+                    id: String::new(),
+                });
+                let marker = format!("{} ", "#".repeat(heading_rank(level)));
+                self.push_span(Span::styled(marker, self.heading_style(level)));
+            }
+            Tag::BlockQuote => {
+                self.flush_line();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush_line();
+                // フェンスで囲んだコードは次の行から始まる
+                let fenced = matches!(kind, CodeBlockKind::Fenced(_));
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .next()
+                        .filter(|lang| !lang.is_empty())
+                        .map(str::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+                self.code_block = Some(CodeBlockBuffer {
+                    lang,
+                    text: String::new(),
+                    source_line: self.source_line + usize::from(fenced),
+                });
+            }
+            Tag::HtmlBlock => self.flush_line(),
+            Tag::List(start) => {
+                self.flush_line();
+                // 入れ子のリストは親の項目の途中から始まるので空行を挟まない
+                self.lists.push(ListLevel {
+                    next_number: start,
+                    marker_width: 2,
+                });
+            }
+            Tag::Item => {
+                self.flush_line();
+                if let Some(level) = self.lists.last_mut() {
+                    let marker = match &mut level.next_number {
+                        Some(number) => {
+                            let marker = format!("{}. ", number);
+                            *number += 1;
+                            marker
+                        }
+                        None => "• ".to_string(),
+                    };
+                    level.marker_width = marker.width();
+                    self.pending_marker = Some(marker);
+                }
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.flush_line();
+                let style = Style::default().fg(self.theme.link);
+                self.push_span(Span::styled(format!("[^{}]: ", label), style));
+            }
+            Tag::Table(alignments) => {
+                self.flush_line();
+                self.table = Some(TableBuffer {
+                    alignments,
+                    rows: Vec::new(),
+                    header_rows: 0,
+                    current_row: Vec::new(),
+                    current_cell: Vec::new(),
+                });
+            }
+            Tag::TableHead | Tag::TableRow | Tag::TableCell | Tag::MetadataBlock(_) => {}
+            Tag::Emphasis => self
+                .inline_styles
+                .push(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self
+                .inline_styles
+                .push(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self
+                .inline_styles
+                .push(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link { dest_url, .. } => {
+                self.links.push(LinkEntry {
+                    url: dest_url.into_string(),
+                    text: String::new(),
+                    line: self.lines.len(),
+                });
+                self.in_link = true;
+                self.inline_styles.push(
+                    Style::default()
+                        .fg(self.theme.link)
+                        .add_modifier(Modifier::UNDERLINED),
+                );
+            }
+            Tag::Image { .. } => {
+                let style = Style::default().fg(self.theme.comment);
+                self.push_span(Span::styled("[画像: ", style));
+                self.inline_styles.push(style);
+            }
+        }
+    }
+
+    fn end_tag(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.end_block(),
+            TagEnd::Heading(level) => {
+                let width = self.spans.iter().map(Span::width).sum::<usize>();
+                self.flush_line();
+                self.heading = None;
+                // 見出しの大きさの代わりに、H1・H2には下線を引く
+                let underline = match level {
+                    HeadingLevel::H1 => Some("═"),
+                    HeadingLevel::H2 => Some("─"),
+                    _ => None,
+                };
+                if let Some(underline) = underline {
+                    let style = Style::default().fg(self.theme.border);
+                    self.push_span(Span::styled(underline.repeat(width), style));
+                }
+                self.end_block();
+            }
+            TagEnd::BlockQuote => {
+                self.flush_line();
+                // 引用内の最後の空行は引用の外の空行に置き換える
+                while self.lines.last().is_some_and(is_blank) {
+                    self.lines.pop();
+                }
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.blank_line();
+            }
+            TagEnd::CodeBlock => {
+                if let Some(code_block) = self.code_block.take() {
+                    self.render_code_block(code_block);
+                }
+                self.end_block();
+            }
+            TagEnd::HtmlBlock => self.end_block(),
+            TagEnd::List(_) => {
+                self.flush_line();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            TagEnd::Item => {
+                self.flush_line();
+                self.pending_marker = None;
+            }
+            TagEnd::FootnoteDefinition => self.end_block(),
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.render_table(table);
+                }
+                self.end_block();
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    // ヘッダー行はTableRowで囲まれないので、ここで1行として確定させる
+                    let row = std::mem::take(&mut table.current_row);
+                    table.rows.push(row);
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.current_row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::TableCell => {
+                if let Some(table) = &mut self.table {
+                    let cell = std::mem::take(&mut table.current_cell);
+                    table.current_row.push(cell);
+                }
+            }
+            TagEnd::Link => {
+                self.in_link = false;
+                self.inline_styles.pop();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.inline_styles.pop();
+            }
+            TagEnd::Image => {
+                self.inline_styles.pop();
+                let style = Style::default().fg(self.theme.comment);
+                self.push_span(Span::styled("]", style));
+            }
+            TagEnd::MetadataBlock(_) => {}
+        }
+    }
+
+    // 引用やリストの中かどうかに応じた現在の文字スタイル
+    fn current_style(&self) -> Style {
+        let base = match self.heading {
+            Some(level) => self.heading_style(level),
+            None if self.quote_depth > 0 => Style::default().fg(self.theme.comment),
+            None => Style::default().fg(self.theme.fg),
+        };
+        self.inline_styles
+            .iter()
+            .fold(base, |style, inline| style.patch(*inline))
+    }
+
+    // これから描画する文字列が元のMarkdownのどの行のものかを記録する
+    fn mark_source(&mut self) {
+        let column = self.spans.iter().map(|span| span.content.len()).sum();
+        self.source_lines.push(SourceLine {
+            source: self.source_line,
+            line: self.lines.len(),
+            column,
+        });
+    }
+
+    // 見出しやリンクの中の文字列を、アウトラインやリンクの一覧の項目名にする
+    fn push_title_text(&mut self, text: &str) {
+        if self.heading.is_some()
+            && let Some(entry) = self.outline.last_mut()
+        {
+            entry.title.push_str(text);
+        }
+        if self.in_link
+            && let Some(link) = self.links.last_mut()
+        {
+            link.text.push_str(text);
+        }
+    }
+
+    fn heading_style(&self, level: HeadingLevel) -> Style {
+        let color = self.theme.headings[heading_rank(level) - 1];
+        Style::default().fg(color).add_modifier(Modifier::BOLD)
+    }
+
+    // 改行を含むテキストは行ごとに分割して追加する
+    fn push_text(&mut self, text: &str, style: Style) {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.flush_line();
+            }
+            if !part.is_empty() {
+                self.push_span(Span::styled(part.to_string(), style));
+            }
+        }
+    }
+
+    fn push_span(&mut self, span: Span<'static>) {
+        if let Some(table) = &mut self.table {
+            table.current_cell.push(span);
+            return;
+        }
+        if self.spans.is_empty() {
+            self.spans = self.line_prefix();
+        }
+        self.spans.push(span);
+    }
+
+    // 引用のガターとリストのインデントからなる行頭部分
+    fn line_prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = Vec::new();
+        for _ in 0..self.quote_depth {
+            prefix.push(Span::styled("│ ", Style::default().fg(self.theme.border)));
+        }
+        let depth = self.lists.len();
+        for (i, level) in self.lists.iter().enumerate() {
+            let is_innermost = i + 1 == depth;
+            let marker = if is_innermost {
+                self.pending_marker.take()
+            } else {
+                None
+            };
+            match marker {
+                Some(marker) => {
+                    prefix.push(Span::styled(marker, Style::default().fg(self.theme.link)))
+                }
+                None => prefix.push(Span::raw(" ".repeat(level.marker_width))),
+            }
+        }
+        prefix
+    }
+
+    fn flush_line(&mut self) {
+        if !self.spans.is_empty() {
+            let spans = std::mem::take(&mut self.spans);
+            self.lines.push(Line::from(spans));
+        }
+    }
+
+    // ブロック要素の区切りとして空行を1つだけ入れる
+    fn blank_line(&mut self) {
+        self.flush_line();
+        if self.lines.last().is_none_or(|line| is_blank(line)) {
+            return;
+        }
+        let mut gutter = Vec::new();
+        for _ in 0..self.quote_depth {
+            gutter.push(Span::styled("│", Style::default().fg(self.theme.border)));
+        }
+        self.lines.push(Line::from(gutter));
+    }
+
+    fn end_block(&mut self) {
+        self.flush_line();
+        // タイトなリスト内の段落は詰めて表示する
+        if self.lists.is_empty() {
+            self.blank_line();
+        }
+    }
+
+    // フロントマターは本文の前に枠付きの一覧として描画する
+    // `source`はフロントマターの部分の原文で、各項目がどの行に書かれていたかを探すのに使う
+    fn render_front_matter(&mut self, front_matter: &FrontMatter, source: &str) {
+        let border = Style::default().fg(self.theme.border);
+        let key_style = Style::default().fg(self.theme.html_attr);
+        let key_width = front_matter
+            .fields
+            .iter()
+            .map(|field| field.key.width())
+            .max()
+            .unwrap_or(0);
+
+        self.push_span(Span::styled("┌ ", border));
+        self.push_span(Span::styled(
+            format!("{} front matter", front_matter.format.label()),
+            Style::default().fg(self.theme.comment),
+        ));
+        self.flush_line();
+        for field in &front_matter.fields {
+            if let Some(source_line) = source.lines().position(|line| defines_key(line, &field.key)) {
+                self.source_lines.push(SourceLine {
+                    source: source_line,
+                    line: self.lines.len(),
+                    column: 0,
+                });
+            }
+            let padding = " ".repeat(key_width - field.key.width());
+            self.push_span(Span::styled("│ ", border));
+            self.push_span(Span::styled(format!("{}{}  ", field.key, padding), key_style));
+            self.push_span(Span::styled(field.value.clone(), Style::default().fg(self.theme.fg)));
+            self.flush_line();
+        }
+        self.push_span(Span::styled("└", border));
+        self.blank_line();
+    }
+
+    // コードブロックは背景色付きの矩形として描画する
+    fn render_code_block(&mut self, code_block: CodeBlockBuffer) {
+        let code_style = Style::default().fg(self.theme.fg).bg(self.theme.code_bg);
+        // タブは端末によって幅が変わるため空白に展開する
+        let code = code_block.text.trim_end_matches('\n').replace('\t', "    ");
+        let width = code.lines().map(|line| line.width()).max().unwrap_or(0);
+        let width = width.max(code_block.lang.as_deref().map_or(0, str::width));
+
+        if let Some(lang) = &code_block.lang {
+            let label_style = Style::default().fg(self.theme.comment).bg(self.theme.code_bg);
+            let padding = " ".repeat(width - lang.width());
+            self.push_span(Span::styled(format!(" {}{} ", lang, padding), label_style));
+            self.flush_line();
+        }
+        // 言語が分かればハイライトし、背景色だけコードブロックの色で塗る
+        let lines = match code_block.lang.as_deref().and_then(syntax_for_lang) {
+            Some(syntax) => highlight_lines(&code, syntax, code_style),
+            None => code
+                .lines()
+                .map(|line| Line::from(Span::styled(line.to_string(), code_style)))
+                .collect(),
+        };
+        let background = Style::default().bg(self.theme.code_bg);
+        for (i, line) in lines.into_iter().enumerate() {
+            self.source_lines.push(SourceLine {
+                source: code_block.source_line + i,
+                line: self.lines.len(),
+                column: 0,
+            });
+            let padding = " ".repeat(width.saturating_sub(line.width()));
+            self.push_span(Span::styled(" ", background));
+            for span in line.spans {
+                self.push_span(span.patch_style(background));
+            }
+            self.push_span(Span::styled(format!("{} ", padding), background));
+            self.flush_line();
+        }
+    }
+
+    fn render_table(&mut self, table: TableBuffer) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &table.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.iter().map(Span::width).sum());
+            }
+        }
+
+        let border_style = Style::default().fg(self.theme.border);
+        for (row_index, row) in table.rows.into_iter().enumerate() {
+            let is_header = row_index < table.header_rows;
+            let mut cells = row.into_iter();
+            for (i, width) in widths.iter().enumerate() {
+                if i > 0 {
+                    self.push_span(Span::styled(" │ ", border_style));
+                }
+                let cell = cells.next().unwrap_or_default();
+                let cell_width: usize = cell.iter().map(Span::width).sum();
+                let padding = width - cell_width;
+                let (left, right) = match table.alignments.get(i) {
+                    Some(ColumnAlignment::Right) => (padding, 0),
+                    Some(ColumnAlignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                self.push_span(Span::raw(" ".repeat(left)));
+                for span in cell {
+                    let span = if is_header {
+                        span.patch_style(Style::default().add_modifier(Modifier::BOLD))
+                    } else {
+                        span
+                    };
+                    self.push_span(span);
+                }
+                self.push_span(Span::raw(" ".repeat(right)));
+            }
+            self.flush_line();
+
+            if is_header && row_index + 1 == table.header_rows {
+                let separator = widths
+                    .iter()
+                    .map(|width| "─".repeat(*width))
+                    .collect::<Vec<_>>()
+                    .join("─┼─");
+                self.push_span(Span::styled(separator, border_style));
+                self.flush_line();
+            }
+        }
+    }
+}
+
+fn heading_rank(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+// 引用のガターだけの行も空行として扱う
+fn is_blank(line: &Line) -> bool {
+    line.spans.iter().all(|span| span.content.trim_matches('│').trim().is_empty())
+}
+
+// 行頭から`key:`（YAML）または`key =`（TOML）と書かれた行か。"titles:"や入れ子のキーとは区別する
+fn defines_key(line: &str, key: &str) -> bool {
+    line.strip_prefix(key)
+        .is_some_and(|rest| rest.trim_start_matches(' ').starts_with([':', '=']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_front_matter() {
+        let html = markdown_to_html("---\ntitle: x\n---\n\nbody\n");
+        assert_eq!(html, "<p>body</p>\n");
+    }
+
+    #[test]
+    fn finds_front_matter_key_lines() {
+        assert!(defines_key("title: x", "title"));
+        assert!(defines_key("title = \"x\"", "title"));
+        assert!(!defines_key("titles: x", "title"));
+        assert!(!defines_key("  title: x", "title"));
+    }
+
+    #[test]
+    fn keeps_dash_blocks_that_are_not_front_matter() {
+        // コメントだけの`---`のブロックは、水平線と見出しとして本文に残す
+        let html = markdown_to_html("---\n# 見出し\n---\n\nbody\n");
+        assert!(html.contains("<h1 id=\"見出し\">見出し</h1>"), "{}", html);
+        assert_eq!(html.matches("<hr />").count(), 2, "{}", html);
+        assert!(html.contains("<p>body</p>"), "{}", html);
+
+        // 本文の途中の`---`や`+++`で囲まれたブロックも本文のまま
+        let html = markdown_to_html("intro\n\n---\nただの段落\n---\n\n+++\nx = 1\n+++\n\nbody\n");
+        assert!(html.contains("ただの段落"), "{}", html);
+        assert!(html.contains("x = 1"), "{}", html);
+        assert!(html.contains("<p>body</p>"), "{}", html);
+    }
+}
@@ -0,0 +1,107 @@
+// --- 見出しの一覧（アウトライン・目次） ---
+//
+// プレビューでは整形表示の何行目に見出しがあるかを記録し、アウトラインペインや
+// 見出し単位の移動に使う。HTMLへの書き出しでは見出しのidへのリンクで目次を作る。
+
+use crate::export::escape_html;
+use crate::slug::Heading;
+
+/// 整形表示中の見出し
+pub struct OutlineEntry {
+    pub level: usize, // 1〜6
+    pub title: String,
+    pub line: usize, // 整形表示での行番号
+    pub id: String,  // HTMLでの見出しのid（"#見出し"のリンク先）
+}
+
+/// アウトラインペインの状態
+#[derive(Default)]
+pub struct OutlinePane {
+    pub selected: usize,       // 選択中の見出し（OutlineEntryの添字）
+    pub collapsed: Vec<usize>, // 配下の見出しを折りたたんだ見出し
+    pub focused: bool,         // キー操作をペインで受け取る
+}
+
+impl OutlinePane {
+    /// 折りたたまれていない見出しの添字
+    pub fn visible(&self, outline: &[OutlineEntry]) -> Vec<usize> {
+        let mut visible = Vec::new();
+        let mut hidden_below: Option<usize> = None; // この階層より深い見出しは隠す
+        for (i, entry) in outline.iter().enumerate() {
+            if hidden_below.is_some_and(|level| entry.level > level) {
+                continue;
+            }
+            hidden_below = self.collapsed.contains(&i).then_some(entry.level);
+            visible.push(i);
+        }
+        visible
+    }
+
+    /// 表示中の見出しの間で選択を移動する
+    pub fn move_selection(&mut self, outline: &[OutlineEntry], forward: bool, times: usize) {
+        let visible = self.visible(outline);
+        let Some(position) = visible.iter().position(|&i| i == self.selected) else {
+            self.selected = visible.first().copied().unwrap_or(0);
+            return;
+        };
+        let position = if forward {
+            (position + times).min(visible.len() - 1)
+        } else {
+            position.saturating_sub(times)
+        };
+        self.selected = visible[position];
+    }
+
+    /// 選択中の見出しの配下を折りたたむ・展開する
+    pub fn set_collapsed(&mut self, outline: &[OutlineEntry], collapsed: bool) {
+        let has_children = outline
+            .get(self.selected + 1)
+            .zip(outline.get(self.selected))
+            .is_some_and(|(next, entry)| next.level > entry.level);
+        self.collapsed.retain(|&i| i != self.selected);
+        if collapsed && has_children {
+            self.collapsed.push(self.selected);
+        }
+    }
+}
+
+/// `line`行目を含む節の見出し（最後に現れた`line`以前の見出し）
+pub fn section_at(heading_lines: &[usize], line: usize) -> Option<usize> {
+    heading_lines.iter().rposition(|&heading| heading <= line)
+}
+
+/// 目次のHTML。見出しの階層に合わせて入れ子のリストにする
+pub fn toc_html(toc: &[Heading]) -> String {
+    if toc.is_empty() {
+        return String::new();
+    }
+    let base = toc.iter().map(|entry| entry.level).min().unwrap_or(1);
+    let mut html = String::from("<nav class=\"toc\">\n");
+    let mut depth = 0;
+    for entry in toc {
+        let level = entry.level - base + 1;
+        if level > depth {
+            for _ in depth..level {
+                html.push_str("<ul>\n<li>");
+            }
+        } else {
+            html.push_str("</li>\n");
+            for _ in level..depth {
+                html.push_str("</ul>\n</li>\n");
+            }
+            html.push_str("<li>");
+        }
+        depth = level;
+        html.push_str(&format!(
+            "<a href=\"#{}\">{}</a>",
+            escape_html(&entry.id),
+            escape_html(&entry.title)
+        ));
+    }
+    html.push_str("</li>\n");
+    for _ in 1..depth {
+        html.push_str("</ul>\n</li>\n");
+    }
+    html.push_str("</ul>\n</nav>\n");
+    html
+}
@@ -0,0 +1,205 @@
+// --- プレビュー内の検索 ---
+//
+// 表示中のテキストを行ごとに検索し、一致箇所の背景色を変えたテキストを作る。
+// 通常は文字列としてそのまま検索し、正規表現モードではパターンとして解釈する。
+
+use ratatui::prelude::*;
+use regex::{Regex, RegexBuilder};
+
+use crate::ColorScheme;
+
+/// 一致箇所（行番号と、その行の文字列中のバイト範囲）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Default)]
+pub struct Search {
+    pub query: String,
+    pub regex: bool,       // パターンを正規表現として扱う
+    pub ignore_case: bool, // 大文字・小文字を区別しない
+    pub matches: Vec<Match>,
+    pub current: Option<usize>, // 現在選択中の一致箇所
+    pub error: Option<String>,  // 正規表現の構文エラー
+    highlighted: Option<Text<'static>>,
+}
+
+impl Search {
+    /// 一致箇所を強調表示したテキスト。検索していなければNone
+    pub fn highlighted(&self) -> Option<&Text<'static>> {
+        self.highlighted.as_ref()
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.matches.clear();
+        self.current = None;
+        self.error = None;
+        self.highlighted = None;
+    }
+
+    /// `text`を検索し直して強調表示を作り直す。
+    /// 現在の一致箇所は`from_line`以降で最初に見つかったものにする
+    pub fn update(&mut self, text: &Text<'static>, from_line: usize, theme: &ColorScheme) {
+        self.matches.clear();
+        self.current = None;
+        self.error = None;
+        self.highlighted = None;
+        if self.query.is_empty() {
+            return;
+        }
+
+        let pattern = match self.build_regex() {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                // 構文エラーの説明は複数行になるので、最後の要約行だけを使う
+                let message = e.to_string();
+                let summary = message.lines().last().unwrap_or_default().trim();
+                self.error = Some(summary.trim_start_matches("error: ").to_string());
+                return;
+            }
+        };
+        for (line_index, line) in text.lines.iter().enumerate() {
+            let line_text = line_to_string(line);
+            self.matches.extend(
+                pattern
+                    .find_iter(&line_text)
+                    .filter(|m| !m.is_empty()) // 空文字列への一致は表示できないので除く
+                    .map(|m| Match {
+                        line: line_index,
+                        start: m.start(),
+                        end: m.end(),
+                    }),
+            );
+        }
+        if !self.matches.is_empty() {
+            let first = self.matches.iter().position(|m| m.line >= from_line);
+            self.current = Some(first.unwrap_or(0));
+        }
+        self.highlighted = Some(self.highlight(text, theme));
+    }
+
+    /// 次（`forward`がfalseなら前）の一致箇所に移り、その行番号を返す。末尾の次は先頭に戻る
+    pub fn step(&mut self, forward: bool, text: &Text<'static>, theme: &ColorScheme) -> Option<usize> {
+        let count = self.matches.len();
+        let current = self.current?;
+        let next = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        self.current = Some(next);
+        self.highlighted = Some(self.highlight(text, theme));
+        Some(self.matches[next].line)
+    }
+
+    /// `index`番目（範囲外なら最後）の一致箇所を選び、その行番号を返す
+    pub fn select(&mut self, index: usize, text: &Text<'static>, theme: &ColorScheme) -> Option<usize> {
+        let last = self.matches.len().checked_sub(1)?;
+        self.current = Some(index.min(last));
+        self.highlighted = Some(self.highlight(text, theme));
+        self.current_line()
+    }
+
+    /// 現在選択中の一致箇所の行番号
+    pub fn current_line(&self) -> Option<usize> {
+        self.current.map(|i| self.matches[i].line)
+    }
+
+    /// フッターに表示する一致件数（例: "[3/17]"）
+    pub fn counter(&self) -> String {
+        match self.current {
+            Some(i) => format!("[{}/{}]", i + 1, self.matches.len()),
+            None => "[0/0]".to_string(),
+        }
+    }
+
+    /// 検索モードの表示（例: "(regex, ignore case)"）
+    pub fn mode_label(&self) -> String {
+        let mut modes = Vec::new();
+        if self.regex {
+            modes.push("regex");
+        }
+        if self.ignore_case {
+            modes.push("ignore case");
+        }
+        if modes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", modes.join(", "))
+        }
+    }
+
+    pub fn build_regex(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+    }
+
+    fn highlight(&self, text: &Text<'static>, theme: &ColorScheme) -> Text<'static> {
+        let match_style = Style::default().bg(theme.match_bg);
+        let current_style = Style::default()
+            .fg(theme.bg)
+            .bg(theme.current_match_bg)
+            .add_modifier(Modifier::BOLD);
+
+        let mut lines = text.lines.clone();
+        for (i, m) in self.matches.iter().enumerate() {
+            let style = if Some(i) == self.current {
+                current_style
+            } else {
+                match_style
+            };
+            let line = &mut lines[m.line];
+            *line = restyle_range(line, m.start, m.end, style);
+        }
+        Text::from(lines)
+    }
+}
+
+pub fn line_to_string(line: &Line) -> String {
+    line.spans.iter().map(|span| span.content.as_ref()).collect()
+}
+
+// 行の文字列中の`start..end`（バイト位置）に当たる部分にスタイルを重ねる。
+// 範囲がスパンの途中で始まる・終わる場合はスパンを分割する
+pub fn restyle_range(line: &Line<'static>, start: usize, end: usize, style: Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for span in &line.spans {
+        let content = span.content.as_ref();
+        let span_start = offset;
+        let span_end = offset + content.len();
+        offset = span_end;
+
+        if span_end <= start || end <= span_start {
+            spans.push(span.clone());
+            continue;
+        }
+        let from = start.saturating_sub(span_start);
+        let to = (end - span_start).min(content.len());
+        let pieces = [
+            (&content[..from], span.style),
+            (&content[from..to], span.style.patch(style)),
+            (&content[to..], span.style),
+        ];
+        for (piece, piece_style) in pieces {
+            if !piece.is_empty() {
+                spans.push(Span::styled(piece.to_string(), piece_style));
+            }
+        }
+    }
+    Line {
+        spans,
+        style: line.style,
+        alignment: line.alignment,
+    }
+}
@@ -0,0 +1,337 @@
+// --- ローカルのプレビューサーバー ---
+//
+// 指定したディレクトリを127.0.0.1で公開し、`.md`はリクエストのたびにHTML文書に変換して返す。
+// それ以外のファイルはそのまま返し、ディレクトリにはエクスプローラー風の一覧を表示する。
+// 開いているページはServer-Sent Eventsで変更の通知を受け取り、自動で再読み込みする。
+// 閲覧用のサーバーなので、GETとHEADだけを扱い、1リクエストごとに接続を閉じる。
+// DNSリバインディングで外部のページから読まれないよう、Hostが127.0.0.1かlocalhostのリクエストだけに応える。
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::cli::ServeArgs;
+use crate::convert::fallback_title;
+use crate::encoding::read_file;
+use crate::export::{document_title, escape_html, standalone_html};
+use crate::front_matter::split_front_matter;
+use crate::links::{encode_path, resolve_path};
+use crate::markdown::{is_markdown, markdown_to_html};
+use crate::read_sorted_entries;
+use crate::watch::TreeWatcher;
+
+/// 変更の通知を受け取るためのURL
+const EVENTS_PATH: &str = "/__peek/events";
+
+/// ページに埋め込む再読み込み用のスクリプト
+const RELOAD_SCRIPT: &str = r#"<script>
+new EventSource("/__peek/events").onmessage = () => location.reload();
+</script>
+"#;
+
+/// ディレクトリ一覧のスタイル（TUIのファイル一覧と同じ配色）
+const INDEX_STYLE: &str = r#"<style>
+.explorer { list-style: none; padding: 0; border: 1px solid #30363d; border-radius: 6px; }
+.explorer li { margin: 0; padding: 4px 16px; }
+.explorer li + li { margin: 0; border-top: 1px solid #30363d; }
+.explorer li:hover { background: #032252; }
+.explorer a { display: block; color: #c9d1d9; }
+.explorer a.dir { color: #58a6ff; }
+</style>
+"#;
+
+/// 通知を待つ間に送る空のコメントの間隔（切断の検出を兼ねる）
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+type Clients = Arc<Mutex<Vec<Sender<()>>>>;
+
+pub fn run_serve(args: &ServeArgs) -> io::Result<()> {
+    let root = dunce::canonicalize(args.dir.as_deref().unwrap_or(Path::new(".")))?;
+    if !root.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("ディレクトリではありません: {}", root.display()),
+        ));
+    }
+    let listener = TcpListener::bind(("127.0.0.1", args.port))?;
+    let address = listener.local_addr()?;
+    let url = format!("http://{}/", address);
+    let port = address.port();
+
+    // ファイルが変わったら、接続中のすべてのページに通知する
+    let clients: Clients = Arc::default();
+    let watcher = TreeWatcher::new(&root).map_err(io::Error::other)?;
+    let watched_clients = Arc::clone(&clients);
+    thread::spawn(move || {
+        while watcher.wait_for_change() {
+            let mut clients = watched_clients.lock().unwrap_or_else(|e| e.into_inner());
+            clients.retain(|client| client.send(()).is_ok());
+        }
+    });
+
+    eprintln!("{} で待ち受けています（Ctrl-Cで終了）", url);
+    eprintln!("公開するディレクトリ: {}", root.display());
+    if args.open
+        && let Err(e) = opener::open(&url)
+    {
+        eprintln!("警告: ブラウザで開けませんでした: {}", e);
+    }
+
+    let root = Arc::new(root);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let root = Arc::clone(&root);
+        let clients = Arc::clone(&clients);
+        thread::spawn(move || {
+            // 接続が途中で切れても、ほかのリクエストには影響しないので無視する
+            let _ = handle_connection(stream, &root, port, &clients);
+        });
+    }
+    Ok(())
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+    location: Option<String>, // リダイレクト先
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+            location: None,
+        }
+    }
+
+    fn error(status: &'static str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: status.as_bytes().to_vec(),
+            location: None,
+        }
+    }
+
+    fn redirect(location: String) -> Self {
+        Self {
+            status: "301 Moved Permanently",
+            content_type: "text/plain; charset=utf-8",
+            body: Vec::new(),
+            location: Some(location),
+        }
+    }
+
+    fn write_to(&self, stream: &mut TcpStream, head_only: bool) -> io::Result<()> {
+        let mut head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        if let Some(location) = &self.location {
+            head.push_str(&format!("Location: {}\r\n", location));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        if !head_only {
+            stream.write_all(&self.body)?;
+        }
+        stream.flush()
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, root: &Path, port: u16, clients: &Clients) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // ヘッダーはHostだけを使い、ほかは読み飛ばす
+    let mut host = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header != "\r\n" && header != "\n" {
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("host")
+        {
+            host = Some(value.trim().to_ascii_lowercase());
+        }
+        header.clear();
+    }
+    let allowed_hosts = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    if !host.is_some_and(|host| allowed_hosts.contains(&host)) {
+        return Response::error("403 Forbidden").write_to(&mut stream, false);
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Response::error("400 Bad Request").write_to(&mut stream, false);
+    };
+    let head_only = method == "HEAD";
+    if method != "GET" && !head_only {
+        return Response::error("405 Method Not Allowed").write_to(&mut stream, false);
+    }
+
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    if path == EVENTS_PATH {
+        return stream_events(stream, clients);
+    }
+    route(root, path).write_to(&mut stream, head_only)
+}
+
+// 変更があるたびに"reload"を送り続ける。ページを閉じると書き込みに失敗して終わる
+fn stream_events(mut stream: TcpStream, clients: &Clients) -> io::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    clients.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+    )?;
+    stream.flush()?;
+    loop {
+        let message: &[u8] = match rx.recv_timeout(KEEP_ALIVE) {
+            Ok(()) => b"data: reload\n\n",
+            Err(RecvTimeoutError::Timeout) => b": keep-alive\n\n",
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        stream.write_all(message)?;
+        stream.flush()?;
+    }
+}
+
+fn route(root: &Path, request_path: &str) -> Response {
+    // URLのパスをディレクトリ内のパスに対応づけ、外に出るものは拒否する
+    let relative = request_path.trim_start_matches('/');
+    let path = if relative.is_empty() {
+        root.to_path_buf()
+    } else {
+        resolve_path(root, relative)
+    };
+    let Ok(path) = dunce::canonicalize(&path) else {
+        return Response::error("404 Not Found");
+    };
+    if !path.starts_with(root) {
+        return Response::error("403 Forbidden");
+    }
+
+    if path.is_dir() {
+        // 相対リンクが正しく解決されるよう、ディレクトリのURLは"/"で終わらせる
+        // "//example.com"のようなURLにならないよう、正規化したパスから作る
+        if !request_path.ends_with('/') {
+            let relative = url_path(path.strip_prefix(root).unwrap_or(&path));
+            let location = if relative.is_empty() {
+                "/".to_string()
+            } else {
+                format!("/{}/", encode_path(&relative))
+            };
+            return Response::redirect(location);
+        }
+        return match directory_index(root, &path) {
+            Ok(html) => Response::ok("text/html; charset=utf-8", html.into_bytes()),
+            Err(_) => Response::error("500 Internal Server Error"),
+        };
+    }
+
+    let result = if is_markdown(&path) {
+        read_file(&path).map(|decoded| decoded.text).map(|markdown_input| {
+            let body = markdown_to_html(&markdown_input) + RELOAD_SCRIPT;
+            let title = document_title(&markdown_input).unwrap_or_else(|| fallback_title(&path));
+            Response::ok(
+                "text/html; charset=utf-8",
+                standalone_html(
+                    &body,
+                    &title,
+                    split_front_matter(&markdown_input).0.as_ref(),
+                    None,
+                )
+                .into_bytes(),
+            )
+        })
+    } else {
+        fs::read(&path).map(|body| Response::ok(content_type(&path), body))
+    };
+    result.unwrap_or_else(|e| match e.kind() {
+        io::ErrorKind::NotFound => Response::error("404 Not Found"),
+        io::ErrorKind::PermissionDenied => Response::error("403 Forbidden"),
+        _ => Response::error("500 Internal Server Error"),
+    })
+}
+
+// エクスプローラーと同じく、ディレクトリを先に名前順で並べる
+fn directory_index(root: &Path, dir: &Path) -> io::Result<String> {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    let title = format!("/{}", url_path(relative));
+
+    let mut items = String::new();
+    if dir != root {
+        items.push_str("<li><a class=\"dir\" href=\"../\">../</a></li>\n");
+    }
+    for path in read_sorted_entries(dir)? {
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let (class, suffix) = if path.is_dir() { ("dir", "/") } else { ("file", "") };
+        items.push_str(&format!(
+            "<li><a class=\"{}\" href=\"{}{}\">{}{}</a></li>\n",
+            class,
+            encode_path(&name),
+            suffix,
+            escape_html(&name),
+            suffix
+        ));
+    }
+
+    let body = format!(
+        "{}<h1>{}</h1>\n<ul class=\"explorer\">\n{}</ul>\n{}",
+        INDEX_STYLE,
+        escape_html(&title),
+        items,
+        RELOAD_SCRIPT
+    );
+    Ok(standalone_html(&body, &title, None, None))
+}
+
+fn url_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "toml" | "yaml" | "yml" | "rs" | "py" | "sh" => "text/plain; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
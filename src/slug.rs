@@ -0,0 +1,148 @@
+// --- 見出しのid（アンカー） ---
+//
+// GitHubと同じ規則で見出しの文字列からidを作る。小文字にし、文字・数字・"_"・"-"・空白
+// 以外を取り除いてから空白を"-"に置き換える。日本語などの文字はそのまま残し、
+// 「」や。のような記号は取り除く。同じidが続くときは"-1"、"-2"…を付けて区別する。
+
+use std::collections::HashMap;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+/// 文書内で使用済みのidを覚えておき、重複しないidを作る
+#[derive(Default)]
+pub struct Slugger {
+    occurrences: HashMap<String, usize>, // id → 末尾に付けた最後の番号
+}
+
+impl Slugger {
+    /// 見出しの文字列から、まだ使われていないidを作る
+    pub fn slug(&mut self, title: &str) -> String {
+        let base = slugify(title);
+        let mut slug = base.clone();
+        if let Some(&count) = self.occurrences.get(&base) {
+            let mut count = count;
+            // "a-1"という見出しが先にあった場合などに備え、空いている番号まで進める
+            while self.occurrences.contains_key(&slug) {
+                count += 1;
+                slug = format!("{}-{}", base, count);
+            }
+            self.occurrences.insert(base, count);
+        }
+        self.occurrences.insert(slug.clone(), 0);
+        slug
+    }
+
+    /// `{#id}`で明示されたidを使用済みにする
+    pub fn reserve(&mut self, id: &str) {
+        self.occurrences.entry(id.to_string()).or_insert(0);
+    }
+}
+
+/// GitHubの規則で見出しの文字列をidにする（重複は考慮しない）
+pub fn slugify(title: &str) -> String {
+    title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|&c| c.is_alphanumeric() || is_mark(c) || matches!(c, '_' | '-' | ' ' | '‿' | '⁀' | '＿'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+// 結合文字（アクセント記号や結合用の濁点・半濁点など）は直前の文字の一部として残す
+fn is_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036f}' | '\u{1ab0}'..='\u{1aff}' | '\u{20d0}'..='\u{20ff}' | '\u{3099}'..='\u{309a}' | '\u{fe00}'..='\u{fe0f}')
+}
+
+/// idを付けた見出し
+pub struct Heading {
+    pub level: usize, // 1〜6
+    pub title: String,
+    pub id: String,
+    pub end: usize, // 見出しの終了イベントの位置
+}
+
+/// 見出しの開始イベントにidを付け、見出しの一覧を返す。`{#id}`の指定があればそれを使う
+pub fn assign_heading_ids(events: &mut [Event]) -> Vec<Heading> {
+    let mut slugger = Slugger::default();
+    // 明示されたidは、文書内の位置に関係なく自動のidより優先する
+    for event in events.iter() {
+        if let Event::Start(Tag::Heading { id: Some(id), .. }) = event {
+            slugger.reserve(id);
+        }
+    }
+
+    // 見出しの文字列は開始イベントより後に現れるので、見出しごとに終了まで読んでからidを決める
+    let mut headings = Vec::new();
+    let mut start = None;
+    let mut title = String::new();
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { .. }) => {
+                start = Some(i);
+                title.clear();
+            }
+            Event::Text(text) | Event::Code(text) if start.is_some() => title.push_str(text),
+            &Event::End(TagEnd::Heading(level)) => {
+                let Some(start) = start.take() else {
+                    continue;
+                };
+                let id = match &mut events[start] {
+                    Event::Start(Tag::Heading { id: Some(id), .. }) => id.to_string(),
+                    Event::Start(Tag::Heading { id, .. }) => {
+                        let slug = slugger.slug(&title);
+                        *id = Some(CowStr::from(slug.clone()));
+                        slug
+                    }
+                    _ => continue,
+                };
+                headings.push(Heading {
+                    level: level as usize,
+                    title: title.trim().to_string(),
+                    id,
+                    end: i,
+                });
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_matches_github() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  API_v2 -- 概要 "), "api_v2----概要");
+        assert_eq!(slugify("日本語の見出し"), "日本語の見出し");
+        // 全角のかぎ括弧・句読点は取り除き、全角の英数字は小文字にして残す
+        assert_eq!(slugify("「設定」について。"), "設定について");
+        assert_eq!(slugify("ＡＢＣ、テスト"), "ａｂｃテスト");
+    }
+
+    #[test]
+    fn slugify_keeps_combining_marks() {
+        // 結合用の濁点（U+3099）とアクセント記号（U+0301）は取り除かない
+        assert_eq!(slugify("か\u{3099}いよう"), "か\u{3099}いよう");
+        assert_eq!(slugify("Cafe\u{301} Menu"), "cafe\u{301}-menu");
+    }
+
+    #[test]
+    fn slugger_numbers_duplicates() {
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.slug("a"), "a");
+        assert_eq!(slugger.slug("a"), "a-1");
+        assert_eq!(slugger.slug("a-1"), "a-1-1");
+        assert_eq!(slugger.slug("a"), "a-2");
+    }
+
+    #[test]
+    fn slugger_skips_reserved_ids() {
+        let mut slugger = Slugger::default();
+        slugger.reserve("intro");
+        assert_eq!(slugger.slug("Intro"), "intro-1");
+    }
+}
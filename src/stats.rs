@@ -0,0 +1,216 @@
+// --- 執筆用の文字数の統計 ---
+//
+// HTMLのタグや記法の記号を含まない、整形表示される本文だけを数える。
+// 空白を除いた文字数（全角・半角の内訳）、400字詰め原稿用紙の枚数、英単語数、文の数、
+// 読むのにかかるおおよその時間と、見出しごとの文字数を求める。
+
+use pulldown_cmark::{Event, Tag, TagEnd};
+use unicode_width::UnicodeWidthChar;
+
+use crate::markdown::markdown_events;
+
+/// 原稿用紙1行のマス数と、1枚の行数
+const MANUSCRIPT_COLUMNS: usize = 20;
+const MANUSCRIPT_ROWS: usize = 20;
+
+/// 1分間に読める量（日本語は全角文字数、英語は単語数）
+const FULL_WIDTH_PER_MINUTE: usize = 500;
+const WORDS_PER_MINUTE: usize = 200;
+
+/// 文の終わりを表す記号（"."は後ろが空白か行末のときだけ）
+const SENTENCE_ENDS: &[char] = &['。', '．', '！', '？', '!', '?'];
+
+/// 文末の記号の後ろに続いても、新しい文とはみなさない閉じ括弧など
+const CLOSERS: &[char] = &['」', '』', '）', '】', ')', '"', '\'', '”', '’'];
+
+#[derive(Default, Clone, Copy)]
+pub struct TextStats {
+    pub chars: usize,      // 空白を除いた文字数
+    pub full_width: usize, // うち全角（表示幅2）の文字
+    pub words: usize,      // 英単語（ASCIIの英字を含む並び）
+    pub sentences: usize,
+    manuscript_rows: usize, // 原稿用紙に書いたときの行数
+}
+
+impl TextStats {
+    pub fn half_width(&self) -> usize {
+        self.chars - self.full_width
+    }
+
+    /// 400字詰め原稿用紙の枚数
+    pub fn manuscript_pages(&self) -> f64 {
+        self.manuscript_rows as f64 / MANUSCRIPT_ROWS as f64
+    }
+
+    /// 読むのにかかるおおよその分数（1分未満は1分とする）
+    pub fn reading_minutes(&self) -> usize {
+        if self.chars == 0 {
+            return 0;
+        }
+        let minutes = self.full_width as f64 / FULL_WIDTH_PER_MINUTE as f64
+            + self.words as f64 / WORDS_PER_MINUTE as f64;
+        (minutes.ceil() as usize).max(1)
+    }
+
+    fn plus(mut self, other: &TextStats) -> Self {
+        self.chars += other.chars;
+        self.full_width += other.full_width;
+        self.words += other.words;
+        self.sentences += other.sentences;
+        self.manuscript_rows += other.manuscript_rows;
+        self
+    }
+
+    // 段落などのまとまり1つ分を加える。見出しは文として数えない
+    fn add_block(&mut self, text: &str, is_heading: bool) {
+        for (i, line) in text.lines().enumerate() {
+            // 原稿用紙では段落の最初を1字下げ、半角文字は1マスに2文字入れる
+            let mut halves: usize = if i == 0 && !is_heading { 2 } else { 0 };
+            for c in line.chars().filter(|c| !c.is_whitespace()) {
+                self.chars += 1;
+                if c.width() == Some(2) {
+                    self.full_width += 1;
+                    halves += 2;
+                } else {
+                    halves += 1;
+                }
+            }
+            let cells = halves.div_ceil(2);
+            self.manuscript_rows += cells.div_ceil(MANUSCRIPT_COLUMNS).max(1);
+        }
+        self.words += count_words(text);
+        if !is_heading {
+            self.sentences += count_sentences(text);
+        }
+    }
+}
+
+/// 文書全体と、見出しごと（配下の見出しを含む）の統計
+#[derive(Default)]
+pub struct WritingStats {
+    pub total: TextStats,
+    pub sections: Vec<TextStats>, // 見出しの順（アウトラインの項目と対応する）
+}
+
+/// Markdownの本文を数える。フロントマター・HTMLのタグ・記法の記号は含めない
+pub fn markdown_stats(markdown_input: &str) -> WritingStats {
+    let mut blocks: Vec<(String, bool)> = Vec::new(); // (本文, 見出しか)
+    let mut headings: Vec<(usize, usize)> = Vec::new(); // (見出しの階層, 最初のまとまりの位置)
+    let mut block = String::new();
+    for event in markdown_events(markdown_input) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                flush_block(&mut block, false, &mut blocks);
+                headings.push((level as usize, blocks.len()));
+            }
+            Event::End(TagEnd::Heading(_)) => flush_block(&mut block, true, &mut blocks),
+            Event::End(
+                TagEnd::Paragraph | TagEnd::CodeBlock | TagEnd::Item | TagEnd::TableCell | TagEnd::BlockQuote,
+            ) => flush_block(&mut block, false, &mut blocks),
+            Event::Text(text) | Event::Code(text) => block.push_str(&text),
+            Event::SoftBreak => block.push(' '),
+            Event::HardBreak => block.push('\n'),
+            _ => {}
+        }
+    }
+    flush_block(&mut block, false, &mut blocks);
+
+    let block_stats = blocks
+        .iter()
+        .map(|(text, is_heading)| {
+            let mut stats = TextStats::default();
+            stats.add_block(text, *is_heading);
+            stats
+        })
+        .collect::<Vec<_>>();
+    let sum = |range: &[TextStats]| range.iter().fold(TextStats::default(), |total, stats| total.plus(stats));
+
+    // 見出しの節は、同じか上の階層の次の見出しの手前まで
+    let sections = headings
+        .iter()
+        .enumerate()
+        .map(|(i, &(level, start))| {
+            let end = headings[i + 1..]
+                .iter()
+                .find(|(next_level, _)| *next_level <= level)
+                .map_or(blocks.len(), |&(_, next_start)| next_start);
+            sum(&block_stats[start..end])
+        })
+        .collect();
+    WritingStats {
+        total: sum(&block_stats),
+        sections,
+    }
+}
+
+/// Markdown以外のテキストを数える。空行で区切られた部分を段落とみなす
+pub fn plain_stats(text: &str) -> WritingStats {
+    let mut total = TextStats::default();
+    for paragraph in text.split("\n\n").filter(|paragraph| !paragraph.trim().is_empty()) {
+        total.add_block(paragraph.trim_matches('\n'), false);
+    }
+    WritingStats {
+        total,
+        sections: Vec::new(),
+    }
+}
+
+fn flush_block(block: &mut String, is_heading: bool, blocks: &mut Vec<(String, bool)>) {
+    if !block.trim().is_empty() {
+        blocks.push((std::mem::take(block), is_heading));
+    }
+    block.clear();
+}
+
+// "don't"や"e-mail"は1語として数える。数字だけの並びは単語にしない
+fn count_words(text: &str) -> usize {
+    text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '\'' || c == '-'))
+        .filter(|word| word.chars().any(|c| c.is_ascii_alphabetic()))
+        .count()
+}
+
+// 文末の記号の並び（"？！"など）を1文と数え、記号で終わらない最後の文も1文とする
+fn count_sentences(text: &str) -> usize {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut sentences = 0;
+    let mut in_sentence = false;
+    for (i, &c) in chars.iter().enumerate() {
+        let is_end = SENTENCE_ENDS.contains(&c)
+            || (c == '.' && chars.get(i + 1).is_none_or(|next| next.is_whitespace() || CLOSERS.contains(next)));
+        if is_end {
+            if in_sentence {
+                sentences += 1;
+            }
+            in_sentence = false;
+        } else if !c.is_whitespace() && !CLOSERS.contains(&c) {
+            in_sentence = true;
+        }
+    }
+    sentences + usize::from(in_sentence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_japanese_sentences() {
+        assert_eq!(count_sentences(""), 0);
+        assert_eq!(count_sentences("今日は晴れ。明日は雨。"), 2);
+        // 続けて並んだ文末の記号は1つの文の終わりとする
+        assert_eq!(count_sentences("本当？！ すごい！！"), 2);
+        // 閉じ括弧の中の句点でも文は終わるが、閉じ括弧だけでは文にしない
+        assert_eq!(count_sentences("「行こう。」と言った。"), 2);
+        assert_eq!(count_sentences("「はい。」"), 1);
+        // 文末の記号で終わらない最後の文も数える
+        assert_eq!(count_sentences("一文目。句点のない二文目"), 2);
+    }
+
+    #[test]
+    fn counts_english_sentences() {
+        // 数字の中や単語の途中の"."では文を区切らない
+        assert_eq!(count_sentences("Version 1.5 is out. See example.com now."), 2);
+        assert_eq!(count_sentences("He said \"hi.\" Then he left!"), 2);
+        assert_eq!(count_sentences("Really?! Yes."), 2);
+    }
+}
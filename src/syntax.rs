@@ -0,0 +1,136 @@
+// --- コードのシンタックスハイライト ---
+//
+// 文法とテーマはtwo-faceに同梱されたものを使うので、オフラインでも動作する。
+// 背景色は呼び出し側の配色に合わせるため、ここでは文字色と装飾だけを決める。
+
+use std::{path::Path, sync::OnceLock};
+
+use ratatui::prelude::*;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, Theme},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+use two_face::theme::EmbeddedThemeName;
+
+/// ハイライトするファイルサイズの上限。これより大きいファイルはプレーン表示にする
+const MAX_HIGHLIGHT_BYTES: usize = 1024 * 1024;
+
+// GitHub Darkの配色と馴染むテーマ
+const SYNTAX_THEME: EmbeddedThemeName = EmbeddedThemeName::VisualStudioDarkPlus;
+
+struct Highlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+// 文法の読み込みには時間がかかるので、最初に必要になったときに一度だけ行う
+fn highlighter() -> &'static Highlighter {
+    static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+    HIGHLIGHTER.get_or_init(|| Highlighter {
+        syntaxes: two_face::syntax::extra_newlines(),
+        theme: two_face::theme::extra().get(SYNTAX_THEME).clone(),
+    })
+}
+
+/// ハイライトに使う文法
+#[derive(Clone, Copy)]
+pub struct Syntax(&'static SyntaxReference);
+
+/// フェンスの情報文字列（`rust`, `sh`, `toml`など）から文法を探す
+pub fn syntax_for_lang(lang: &str) -> Option<Syntax> {
+    let syntaxes = &highlighter().syntaxes;
+    syntaxes
+        .find_syntax_by_token(lang)
+        .or_else(|| syntaxes.find_syntax_by_token(&lang.to_ascii_lowercase()))
+        .filter(|syntax| !is_plain_text(syntax))
+        .map(Syntax)
+}
+
+/// ファイル名・拡張子、見つからなければ1行目（シバンなど）から文法を探す
+fn syntax_for_file(path: &Path, content: &str) -> Option<Syntax> {
+    let syntaxes = &highlighter().syntaxes;
+    let by_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| syntaxes.find_syntax_by_extension(name));
+    let by_extension = || {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| syntaxes.find_syntax_by_extension(ext))
+    };
+    let by_first_line = || {
+        content
+            .lines()
+            .next()
+            .and_then(|line| syntaxes.find_syntax_by_first_line(line))
+    };
+    by_name
+        .or_else(by_extension)
+        .or_else(by_first_line)
+        .filter(|syntax| !is_plain_text(syntax))
+        .map(Syntax)
+}
+
+/// ソースファイル全体を言語に応じてハイライトする。
+/// 言語が分からないファイルや巨大なファイルは`fallback`のスタイルでそのまま表示する
+pub fn highlight_source(file_path: &Path, content: &str, fallback: Style) -> Text<'static> {
+    match syntax_for_file(file_path, content) {
+        Some(syntax) if content.len() <= MAX_HIGHLIGHT_BYTES => {
+            Text::from(highlight_lines(content, syntax, fallback))
+        }
+        _ => Text::styled(content.to_string(), fallback),
+    }
+}
+
+fn is_plain_text(syntax: &SyntaxReference) -> bool {
+    syntax.name == "Plain Text"
+}
+
+/// コードを行ごとのハイライト済みテキストに変換する。
+/// 解析に失敗した場合はそれ以降を`fallback`のスタイルで表示する
+pub fn highlight_lines(code: &str, syntax: Syntax, fallback: Style) -> Vec<Line<'static>> {
+    let highlighter = highlighter();
+    let mut lines = HighlightLines::new(syntax.0, &highlighter.theme);
+    let mut failed = false;
+    let mut result = Vec::new();
+
+    for line in LinesWithEndings::from(code) {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let regions = if failed {
+            None
+        } else {
+            lines.highlight_line(line, &highlighter.syntaxes).ok()
+        };
+        let Some(regions) = regions else {
+            failed = true;
+            result.push(Line::from(Span::styled(content.to_string(), fallback)));
+            continue;
+        };
+
+        let spans = regions
+            .into_iter()
+            .map(|(style, text)| (style, text.trim_end_matches(['\n', '\r'])))
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(style, text)| Span::styled(text.to_string(), convert_style(style)))
+            .collect::<Vec<_>>();
+        result.push(Line::from(spans));
+    }
+    result
+}
+
+fn convert_style(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut converted = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        converted = converted.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        converted = converted.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        converted = converted.add_modifier(Modifier::UNDERLINED);
+    }
+    converted
+}
@@ -0,0 +1,89 @@
+// --- ファイルの変更監視 ---
+//
+// エディタは一時ファイルへの書き込みとリネームで保存することが多く、
+// ファイル自体を監視すると保存のたびに監視が外れてしまう。
+// そのため親ディレクトリを監視し、対象のファイル名に関するイベントだけを拾う。
+// `peek serve`ではディレクトリ以下全体を監視する。
+
+use std::{
+    ffi::OsString,
+    path::Path,
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher, // Dropすると監視が止まるので保持しておく
+    events: Receiver<notify::Result<Event>>,
+    file_name: OsString,
+}
+
+impl FileWatcher {
+    pub fn new(file_path: &Path) -> notify::Result<Self> {
+        let file_path = dunce::canonicalize(file_path)?;
+        let (Some(parent), Some(file_name)) = (file_path.parent(), file_path.file_name()) else {
+            return Err(notify::Error::path_not_found());
+        };
+
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            file_name: file_name.to_os_string(),
+        })
+    }
+
+    /// 前回の呼び出し以降に対象ファイルが書き換えられていればtrueを返す。
+    /// 溜まっているイベントはすべて読み捨てるので、連続した書き込みも1回にまとまる
+    pub fn changed(&self) -> bool {
+        let mut changed = false;
+        for event in self.events.try_iter().flatten() {
+            let is_write = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+            let is_target = event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == Some(self.file_name.as_os_str()));
+            changed |= is_write && is_target;
+        }
+        changed
+    }
+}
+
+/// ディレクトリ以下のすべての変更を監視する
+pub struct TreeWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl TreeWatcher {
+    pub fn new(dir: &Path) -> notify::Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// 何かが変更されるまで待つ。監視が止まった場合はfalseを返す
+    pub fn wait_for_change(&self) -> bool {
+        for event in self.events.iter().flatten() {
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                continue;
+            }
+            // 保存では複数のイベントが続けて届くので、落ち着くまで読み捨てて1回にまとめる
+            while self.events.recv_timeout(Duration::from_millis(100)).is_ok() {}
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,115 @@
+// --- テキストの折り返し ---
+//
+// `Paragraph`の折り返しに任せると、論理行と画面上の行の対応が分からず
+// スクロール位置がずれる。そこで表示幅に合わせて自前で折り返し、
+// 各論理行が何行目から始まるかを記録しておく。
+// 英単語は空白で、日本語などの全角文字はどこでも折り返す。
+
+use ratatui::prelude::*;
+use unicode_width::UnicodeWidthChar;
+
+/// タブを展開する幅
+const TAB_WIDTH: usize = 4;
+
+pub struct WrappedText {
+    pub width: u16,
+    pub rows: Vec<Line<'static>>,
+    pub line_starts: Vec<usize>, // 各論理行が始まる画面上の行番号
+}
+
+impl WrappedText {
+    /// 画面上の行が属する論理行
+    pub fn row_to_line(&self, row: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= row)
+            .saturating_sub(1)
+    }
+
+    /// 論理行が始まる画面上の行
+    pub fn line_to_row(&self, line: usize) -> usize {
+        self.line_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.rows.len())
+    }
+}
+
+/// テキストを`width`桁で折り返す
+pub fn wrap_text(text: &Text, width: u16) -> WrappedText {
+    let mut rows = Vec::with_capacity(text.lines.len());
+    let mut line_starts = Vec::with_capacity(text.lines.len());
+    for line in &text.lines {
+        line_starts.push(rows.len());
+        wrap_line(line, text.style, width.max(1) as usize, &mut rows);
+    }
+    WrappedText {
+        width,
+        rows,
+        line_starts,
+    }
+}
+
+fn wrap_line(line: &Line, base_style: Style, width: usize, rows: &mut Vec<Line<'static>>) {
+    let mut row: Vec<(char, Style)> = Vec::new();
+    let mut row_width = 0;
+    let mut break_at: Option<usize> = None; // この位置の手前で折り返せる
+    let mut pushed_any = false;
+
+    let chars = line.spans.iter().flat_map(|span| {
+        let style = base_style.patch(line.style).patch(span.style);
+        span.content.chars().map(move |c| (c, style))
+    });
+    for (c, style) in chars {
+        let (c, repeat) = if c == '\t' { (' ', TAB_WIDTH) } else { (c, 1) };
+        for _ in 0..repeat {
+            let char_width = c.width().unwrap_or(0);
+            if char_width == 2 && !row.is_empty() {
+                break_at = Some(row.len());
+            }
+            if row_width + char_width > width && !row.is_empty() {
+                // 単語の途中なら直前の区切りで折り返し、区切りがなければ文字単位で折り返す
+                let carry = match break_at {
+                    Some(at) if at > 0 => row.split_off(at),
+                    _ => Vec::new(),
+                };
+                rows.push(to_line(&row, line.alignment));
+                pushed_any = true;
+                row = carry;
+                row_width = row.iter().map(|(c, _)| c.width().unwrap_or(0)).sum();
+                break_at = None;
+            }
+            row.push((c, style));
+            row_width += char_width;
+            if c.is_whitespace() || char_width == 2 {
+                break_at = Some(row.len());
+            }
+        }
+    }
+    if !row.is_empty() || !pushed_any {
+        rows.push(to_line(&row, line.alignment));
+    }
+}
+
+// 同じスタイルが続く文字をまとめて1つのスパンにする
+fn to_line(row: &[(char, Style)], alignment: Option<Alignment>) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut current = String::new();
+    let mut current_style = None;
+    for &(c, style) in row {
+        if current_style != Some(style) {
+            if let Some(prev) = current_style {
+                spans.push(Span::styled(std::mem::take(&mut current), prev));
+            }
+            current_style = Some(style);
+        }
+        current.push(c);
+    }
+    if let Some(style) = current_style {
+        spans.push(Span::styled(current, style));
+    }
+    Line {
+        spans,
+        style: Style::default(),
+        alignment,
+    }
+}